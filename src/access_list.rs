@@ -0,0 +1,184 @@
+use crate::events::{AccessModeUpdateLog, AccountListLog, ContractEvent};
+use crate::rbac::Role;
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, env, json_types::U128, near};
+use near_sdk_contract_tools::{owner::Owner, standard::nep297::Event};
+use std::fmt;
+
+/// Which accounts may send, receive, mint and be approved for tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum AccessMode {
+    /// Every account, regardless of the lists.
+    #[default]
+    Open,
+    /// Every account except those on the blocklist.
+    Blocklist,
+    /// Only accounts on the allowlist that are not also blocked.
+    Allowlist,
+}
+
+/// An account's standing under the current access mode.
+#[derive(Clone, Debug)]
+#[near(serializers = [json])]
+pub struct AccountStatus {
+    pub blocked: bool,
+    pub allowed: bool,
+    /// Whether the account may currently take part in transfers, mints and
+    /// approvals.
+    pub permitted: bool,
+}
+
+/// Why an account was rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessError {
+    Blocked(AccountId),
+    NotAllowed(AccountId),
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Blocked(account_id) => write!(f, "Account {account_id} is blocked"),
+            Self::NotAllowed(account_id) => {
+                write!(f, "Account {account_id} is not on the allowlist")
+            }
+        }
+    }
+}
+
+#[near]
+impl MyNftContract {
+    pub fn set_access_mode(&mut self, mode: AccessMode) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_access_mode(mode);
+    }
+
+    pub fn access_mode(&self) -> AccessMode {
+        self.access_mode
+    }
+
+    pub fn block_accounts(&mut self, account_ids: Vec<AccountId>) {
+        Self::require_owner_or_role(Role::Moderator);
+
+        self.internal_block_accounts(account_ids);
+    }
+
+    pub fn unblock_accounts(&mut self, account_ids: Vec<AccountId>) {
+        Self::require_owner_or_role(Role::Moderator);
+
+        self.internal_unblock_accounts(account_ids);
+    }
+
+    pub fn allow_accounts(&mut self, account_ids: Vec<AccountId>) {
+        Self::require_owner_or_role(Role::Moderator);
+
+        self.internal_allow_accounts(account_ids);
+    }
+
+    pub fn disallow_accounts(&mut self, account_ids: Vec<AccountId>) {
+        Self::require_owner_or_role(Role::Moderator);
+
+        self.internal_disallow_accounts(account_ids);
+    }
+
+    pub fn account_status(&self, account_id: AccountId) -> AccountStatus {
+        AccountStatus {
+            blocked: self.blocked_accounts.contains(&account_id),
+            allowed: self.allowed_accounts.contains(&account_id),
+            permitted: self.check_account_access(&account_id).is_ok(),
+        }
+    }
+
+    pub fn blocked_accounts(&self, from_index: Option<U128>, limit: Option<u32>) -> Vec<AccountId> {
+        let it = self
+            .blocked_accounts
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .cloned();
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+
+    pub fn allowed_accounts(&self, from_index: Option<U128>, limit: Option<u32>) -> Vec<AccountId> {
+        let it = self
+            .allowed_accounts
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .cloned();
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_access_mode(&mut self, mode: AccessMode) {
+        self.access_mode = mode;
+
+        ContractEvent::AccessModeUpdate(vec![AccessModeUpdateLog { mode }]).emit();
+    }
+
+    pub(crate) fn internal_block_accounts(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in &account_ids {
+            self.blocked_accounts.insert(account_id.clone());
+        }
+
+        ContractEvent::AccountsBlock(vec![AccountListLog { account_ids }]).emit();
+    }
+
+    pub(crate) fn internal_unblock_accounts(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in &account_ids {
+            self.blocked_accounts.remove(account_id);
+        }
+
+        ContractEvent::AccountsUnblock(vec![AccountListLog { account_ids }]).emit();
+    }
+
+    pub(crate) fn internal_allow_accounts(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in &account_ids {
+            self.allowed_accounts.insert(account_id.clone());
+        }
+
+        ContractEvent::AccountsAllow(vec![AccountListLog { account_ids }]).emit();
+    }
+
+    pub(crate) fn internal_disallow_accounts(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in &account_ids {
+            self.allowed_accounts.remove(account_id);
+        }
+
+        ContractEvent::AccountsDisallow(vec![AccountListLog { account_ids }]).emit();
+    }
+
+    pub(crate) fn check_account_access(&self, account_id: &AccountId) -> Result<(), AccessError> {
+        match self.access_mode {
+            AccessMode::Open => Ok(()),
+            // The contract account holds nested tokens.
+            _ if *account_id == env::current_account_id() => Ok(()),
+            _ if self.blocked_accounts.contains(account_id) => {
+                Err(AccessError::Blocked(account_id.clone()))
+            }
+            AccessMode::Allowlist if !self.allowed_accounts.contains(account_id) => {
+                Err(AccessError::NotAllowed(account_id.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn require_account_access(&self, account_id: &AccountId) {
+        if let Err(e) = self.check_account_access(account_id) {
+            env::panic_str(&e.to_string());
+        }
+    }
+}
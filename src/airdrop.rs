@@ -0,0 +1,472 @@
+use crate::events::{AirdropJobLog, AirdropJobProgressLog, ContractEvent, StorageDepositLog};
+use crate::rbac::Role;
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, NearToken, Promise, env, json_types::U128, near, require};
+use near_sdk_contract_tools::{
+    nft::{Nep145Controller, Nep171Controller, TokenId, TokenMetadata},
+    pause::Pause,
+    standard::nep297::Event,
+};
+
+/// Maximum number of recipients in one `nft_airdrop` or `process_airdrop`
+/// call. Larger drops are split over several calls, or run as a job.
+const MAX_AIRDROP_RECIPIENTS: usize = 30;
+
+/// One recipient of an airdrop. The token is minted as
+/// `<token_id_prefix><token_id_suffix>`.
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh, json])]
+pub struct AirdropRecipient {
+    pub account_id: AccountId,
+    pub token_id_suffix: String,
+}
+
+/// Where storage for recipients that need it is paid from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum AirdropFunding {
+    /// The deposit attached to `nft_airdrop`, or to the calls that create and
+    /// fill an airdrop job. What is left is refunded.
+    AttachedDeposit,
+    /// The storage sponsorship pool. Storage funded this way is sponsored:
+    /// recipients cannot withdraw it, and it goes back to the pool when they
+    /// unregister.
+    SponsorPool,
+}
+
+#[derive(Clone, Debug)]
+#[near(serializers = [json])]
+pub struct AirdropMint {
+    pub account_id: AccountId,
+    pub token_id: TokenId,
+}
+
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh, json])]
+pub struct AirdropSkip {
+    pub account_id: AccountId,
+    pub token_id: TokenId,
+    pub reason: String,
+}
+
+/// Outcome of an `nft_airdrop` call.
+#[derive(Clone, Debug, Default)]
+#[near(serializers = [json])]
+pub struct AirdropSummary {
+    pub minted: Vec<AirdropMint>,
+    pub skipped: Vec<AirdropSkip>,
+    /// Total storage deposit made on behalf of recipients.
+    pub storage_funded: NearToken,
+}
+
+/// An airdrop too large for one transaction. The owner uploads recipients in
+/// chunks, and anyone can call `process_airdrop` to work through them.
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh, json])]
+pub struct AirdropJob {
+    pub token_id_prefix: String,
+    pub metadata: TokenMetadata,
+    pub funding: AirdropFunding,
+    /// Account that attached the storage budget, and gets back what is left
+    /// of it.
+    pub funder: AccountId,
+    /// Deposit left for recipients' storage when funding from attached
+    /// deposits. Refunded to the funder once the job is done.
+    pub storage_budget: NearToken,
+    /// Total storage deposit made on behalf of recipients so far.
+    pub storage_funded: NearToken,
+    /// Number of recipients uploaded.
+    pub recipients: u64,
+    /// Index of the next recipient to process.
+    pub cursor: u64,
+    pub minted: u64,
+    pub skipped: u64,
+    /// Whether all recipients have been uploaded. A sealed job is done once
+    /// `cursor` reaches `recipients`.
+    pub sealed: bool,
+}
+
+#[near]
+impl MyNftContract {
+    /// Mints one token with `metadata` to each recipient, registering or
+    /// topping up recipients' storage from `funding` where needed.
+    /// Recipients that cannot get a token are skipped and listed in the
+    /// summary instead of failing the whole airdrop.
+    #[payable]
+    pub fn nft_airdrop(
+        &mut self,
+        token_id_prefix: String,
+        metadata: TokenMetadata,
+        recipients: Vec<AirdropRecipient>,
+        funding: AirdropFunding,
+    ) -> AirdropSummary {
+        Self::require_owner_or_role(Role::Minter);
+        Self::require_unpaused();
+
+        require!(
+            recipients.len() <= MAX_AIRDROP_RECIPIENTS,
+            format!("Airdrop cannot have more than {MAX_AIRDROP_RECIPIENTS} recipients")
+        );
+
+        require_deposit_matches_funding(funding);
+
+        let mut budget = env::attached_deposit();
+        let mut summary = AirdropSummary::default();
+
+        for recipient in recipients {
+            self.airdrop_recipient(
+                &token_id_prefix,
+                &metadata,
+                recipient,
+                funding,
+                &mut budget,
+                &mut summary,
+            );
+        }
+
+        emit_airdrop_skips(&summary);
+
+        if !budget.is_zero() {
+            Promise::new(env::predecessor_account_id()).transfer(budget);
+        }
+
+        summary
+    }
+
+    /// Creates an airdrop job and returns its ID. With `AttachedDeposit`
+    /// funding, the attached deposit starts the job's storage budget.
+    #[payable]
+    pub fn create_airdrop_job(
+        &mut self,
+        token_id_prefix: String,
+        metadata: TokenMetadata,
+        funding: AirdropFunding,
+    ) -> u64 {
+        Self::require_owner_or_role(Role::Minter);
+        require_deposit_matches_funding(funding);
+        self.require_valid_metadata(&metadata);
+
+        let job_id = self.next_airdrop_job_id;
+        self.next_airdrop_job_id += 1;
+
+        self.airdrop_jobs.insert(
+            job_id,
+            AirdropJob {
+                token_id_prefix,
+                metadata,
+                funding,
+                funder: env::predecessor_account_id(),
+                storage_budget: env::attached_deposit(),
+                storage_funded: NearToken::from_yoctonear(0),
+                recipients: 0,
+                cursor: 0,
+                minted: 0,
+                skipped: 0,
+                sealed: false,
+            },
+        );
+
+        ContractEvent::AirdropJobCreate(vec![AirdropJobLog { job_id }]).emit();
+
+        job_id
+    }
+
+    /// Appends a chunk of recipients to an unsealed job. Any attached deposit
+    /// is added to the job's storage budget, and only the job's funder can
+    /// attach one.
+    #[payable]
+    pub fn add_airdrop_recipients(&mut self, job_id: u64, recipients: Vec<AirdropRecipient>) {
+        Self::require_owner_or_role(Role::Minter);
+
+        let job = self
+            .airdrop_jobs
+            .get_mut(&job_id)
+            .unwrap_or_else(|| env::panic_str("Airdrop job does not exist"));
+        require!(!job.sealed, "Airdrop job is sealed");
+        require_deposit_matches_funding(job.funding);
+        require!(
+            env::attached_deposit().is_zero() || env::predecessor_account_id() == job.funder,
+            "Only the job's funder can add to its storage budget"
+        );
+
+        job.storage_budget = job.storage_budget.saturating_add(env::attached_deposit());
+
+        for recipient in recipients {
+            self.airdrop_job_recipients
+                .insert((job_id, job.recipients), recipient);
+            job.recipients += 1;
+        }
+    }
+
+    /// Marks that all recipients of a job have been uploaded.
+    pub fn seal_airdrop_job(&mut self, job_id: u64) {
+        Self::require_owner_or_role(Role::Minter);
+
+        let mut job = self
+            .airdrop_jobs
+            .get(&job_id)
+            .cloned()
+            .unwrap_or_else(|| env::panic_str("Airdrop job does not exist"));
+        require!(!job.sealed, "Airdrop job is already sealed");
+
+        job.sealed = true;
+        self.save_airdrop_job(job_id, job);
+
+        ContractEvent::AirdropJobSeal(vec![AirdropJobLog { job_id }]).emit();
+    }
+
+    /// Airdrops to the next `limit` recipients of a job and advances its
+    /// cursor. Anyone can call this until the job is done.
+    pub fn process_airdrop(&mut self, job_id: u64, limit: Option<u32>) -> AirdropSummary {
+        Self::require_unpaused();
+
+        let mut job = self
+            .airdrop_jobs
+            .get(&job_id)
+            .cloned()
+            .unwrap_or_else(|| env::panic_str("Airdrop job does not exist"));
+        require!(
+            job.cursor < job.recipients,
+            "Airdrop job has no recipients left to process"
+        );
+
+        let limit = limit.map_or(MAX_AIRDROP_RECIPIENTS, |limit| {
+            (limit as usize).clamp(1, MAX_AIRDROP_RECIPIENTS)
+        });
+        let end = job.recipients.min(job.cursor + limit as u64);
+
+        let mut budget = job.storage_budget;
+        let mut summary = AirdropSummary::default();
+
+        for index in job.cursor..end {
+            let recipient = self
+                .airdrop_job_recipients
+                .remove(&(job_id, index))
+                .unwrap_or_else(|| env::panic_str("Airdrop recipient is missing"));
+            self.airdrop_recipient(
+                &job.token_id_prefix,
+                &job.metadata,
+                recipient,
+                job.funding,
+                &mut budget,
+                &mut summary,
+            );
+        }
+
+        emit_airdrop_skips(&summary);
+
+        for skip in &summary.skipped {
+            self.airdrop_job_skips
+                .insert((job_id, job.skipped), skip.clone());
+            job.skipped += 1;
+        }
+
+        job.cursor = end;
+        job.minted += summary.minted.len() as u64;
+        job.storage_funded = job.storage_funded.saturating_add(summary.storage_funded);
+        job.storage_budget = budget;
+
+        ContractEvent::AirdropJobProgress(vec![AirdropJobProgressLog {
+            job_id,
+            cursor: job.cursor,
+            recipients: job.recipients,
+            minted: job.minted,
+            skipped: job.skipped,
+        }])
+        .emit();
+
+        self.save_airdrop_job(job_id, job);
+
+        summary
+    }
+
+    pub fn airdrop_job(&self, job_id: u64) -> Option<AirdropJob> {
+        self.airdrop_jobs.get(&job_id).cloned()
+    }
+
+    pub fn airdrop_jobs(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<(u64, AirdropJob)> {
+        let it = self
+            .airdrop_jobs
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .map(|(job_id, job)| (*job_id, job.clone()));
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+
+    /// Lists the recipients a job has skipped so far, with the reasons.
+    pub fn airdrop_job_skips(
+        &self,
+        job_id: u64,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<AirdropSkip> {
+        let skipped = self.airdrop_jobs.get(&job_id).map_or(0, |job| job.skipped);
+        let from_index = from_index.map_or(0, |i| i.0 as u64);
+        let end = limit.map_or(skipped, |limit| {
+            skipped.min(from_index.saturating_add(limit.into()))
+        });
+
+        (from_index..end)
+            .filter_map(|index| self.airdrop_job_skips.get(&(job_id, index)).cloned())
+            .collect()
+    }
+}
+
+impl MyNftContract {
+    /// Stores `job`, refunding what is left of its storage budget to the
+    /// funder once it is done.
+    fn save_airdrop_job(&mut self, job_id: u64, mut job: AirdropJob) {
+        if job.sealed && job.cursor == job.recipients && !job.storage_budget.is_zero() {
+            Promise::new(job.funder.clone()).transfer(job.storage_budget);
+            job.storage_budget = NearToken::from_yoctonear(0);
+        }
+
+        self.airdrop_jobs.insert(job_id, job);
+    }
+
+    /// Airdrops one token to `recipient` and records the outcome in
+    /// `summary`.
+    fn airdrop_recipient(
+        &mut self,
+        token_id_prefix: &str,
+        metadata: &TokenMetadata,
+        recipient: AirdropRecipient,
+        funding: AirdropFunding,
+        budget: &mut NearToken,
+        summary: &mut AirdropSummary,
+    ) {
+        let token_id = format!("{token_id_prefix}{}", recipient.token_id_suffix);
+
+        match self.airdrop_one(&recipient.account_id, &token_id, metadata, funding, budget) {
+            Ok(funded) => {
+                summary.storage_funded = summary.storage_funded.saturating_add(funded);
+                summary.minted.push(AirdropMint {
+                    account_id: recipient.account_id,
+                    token_id,
+                });
+            }
+            Err(reason) => {
+                summary.skipped.push(AirdropSkip {
+                    account_id: recipient.account_id,
+                    token_id,
+                    reason,
+                });
+            }
+        }
+    }
+
+    /// Funds the recipient's storage if needed and mints the token. Returns
+    /// the amount deposited to the recipient's storage account.
+    ///
+    /// A panic would revert the whole batch, so everything that could make
+    /// the mint fail is checked first and reported as an error instead.
+    fn airdrop_one(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        metadata: &TokenMetadata,
+        source: AirdropFunding,
+        budget: &mut NearToken,
+    ) -> Result<NearToken, String> {
+        self.check_account_access(account_id)
+            .map_err(|e| e.to_string())?;
+
+        if self.token_owner(token_id).is_some() {
+            return Err("token already exists".to_string());
+        }
+
+        self.metadata_policy
+            .check(metadata)
+            .map_err(|e| e.to_string())?;
+
+        let bounds = self.get_storage_balance_bounds();
+        let balance = self.get_storage_balance(account_id).ok();
+
+        // Keep enough available for the new token, and at least the minimum
+        // balance.
+        let required = bounds.min.max(
+            self.nft_storage_cost_estimate(
+                token_id.clone(),
+                metadata.clone(),
+                account_id.clone(),
+                None,
+            )
+            .cost,
+        );
+        let mut funding = balance.as_ref().map_or(required, |balance| {
+            required.saturating_sub(balance.available)
+        });
+        if let Some(max) = bounds.max {
+            let total = balance
+                .as_ref()
+                .map_or(NearToken::from_yoctonear(0), |balance| balance.total);
+            funding = funding.min(max.saturating_sub(total));
+        }
+
+        let available = balance
+            .as_ref()
+            .map_or(NearToken::from_yoctonear(0), |balance| balance.available)
+            .saturating_add(funding);
+        if available < required {
+            return Err("storage balance is at its maximum".to_string());
+        }
+
+        match source {
+            AirdropFunding::AttachedDeposit if funding > *budget => {
+                return Err("not enough funds left for storage".to_string());
+            }
+            AirdropFunding::SponsorPool if !funding.is_zero() => self
+                .check_sponsorship(account_id, funding)
+                .map_err(|e| e.to_string())?,
+            _ => {}
+        }
+
+        if !funding.is_zero() {
+            self.deposit_to_storage_account(account_id, funding)
+                .map_err(|e| e.to_string())?;
+
+            let log = vec![StorageDepositLog {
+                account_id: account_id.clone(),
+                amount: funding,
+            }];
+            match source {
+                AirdropFunding::AttachedDeposit => {
+                    *budget = budget.saturating_sub(funding);
+                    ContractEvent::StorageDeposit(log).emit();
+                }
+                AirdropFunding::SponsorPool => {
+                    self.record_sponsored_storage(account_id, funding);
+                    ContractEvent::StorageSponsor(log).emit();
+                }
+            }
+        }
+
+        self.mint_token(token_id, account_id, metadata, &[]);
+
+        Ok(funding)
+    }
+}
+
+/// Reports all recipients skipped by one call in a single event.
+fn emit_airdrop_skips(summary: &AirdropSummary) {
+    if !summary.skipped.is_empty() {
+        ContractEvent::AirdropSkip(summary.skipped.clone()).emit();
+    }
+}
+
+fn require_deposit_matches_funding(funding: AirdropFunding) {
+    require!(
+        funding == AirdropFunding::AttachedDeposit || env::attached_deposit().is_zero(),
+        "Deposits are only accepted for airdrops funded by attached deposits"
+    );
+}
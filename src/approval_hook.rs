@@ -0,0 +1,71 @@
+use crate::MyNftContract;
+use crate::events::{ContractEvent, NftApproveLog, NftRevokeAllLog, NftRevokeLog};
+use near_sdk::env;
+use near_sdk_contract_tools::{hook::Hook, nft::*, standard::nep297::Event};
+
+/// Emits an event for every NEP-178 approval change.
+pub struct ApprovalHook;
+
+impl Hook<MyNftContract, Nep178Approve<'_>> for ApprovalHook {
+    fn hook<R>(
+        contract: &mut MyNftContract,
+        approve: &Nep178Approve<'_>,
+        f: impl FnOnce(&mut MyNftContract) -> R,
+    ) -> R {
+        contract.require_account_access(&approve.current_owner_id.clone().into_owned());
+        contract.require_account_access(&approve.account_id.clone().into_owned());
+
+        let r = f(contract);
+
+        let approval_id = contract
+            .get_approval_id_for(&approve.token_id, &approve.account_id)
+            .unwrap_or_else(|| env::panic_str("Approval was not recorded"));
+
+        ContractEvent::NftApprove(vec![NftApproveLog {
+            token_id: approve.token_id.clone(),
+            owner_id: approve.current_owner_id.clone().into_owned(),
+            account_id: approve.account_id.clone().into_owned(),
+            approval_id,
+        }])
+        .emit();
+
+        r
+    }
+}
+
+impl Hook<MyNftContract, Nep178Revoke<'_>> for ApprovalHook {
+    fn hook<R>(
+        contract: &mut MyNftContract,
+        revoke: &Nep178Revoke<'_>,
+        f: impl FnOnce(&mut MyNftContract) -> R,
+    ) -> R {
+        let r = f(contract);
+
+        ContractEvent::NftRevoke(vec![NftRevokeLog {
+            token_id: revoke.token_id.clone(),
+            owner_id: revoke.current_owner_id.clone().into_owned(),
+            account_id: revoke.account_id.clone().into_owned(),
+        }])
+        .emit();
+
+        r
+    }
+}
+
+impl Hook<MyNftContract, Nep178RevokeAll<'_>> for ApprovalHook {
+    fn hook<R>(
+        contract: &mut MyNftContract,
+        revoke_all: &Nep178RevokeAll<'_>,
+        f: impl FnOnce(&mut MyNftContract) -> R,
+    ) -> R {
+        let r = f(contract);
+
+        ContractEvent::NftRevokeAll(vec![NftRevokeAllLog {
+            token_id: revoke_all.token_id.clone(),
+            owner_id: revoke_all.current_owner_id.clone().into_owned(),
+        }])
+        .emit();
+
+        r
+    }
+}
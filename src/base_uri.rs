@@ -0,0 +1,176 @@
+//! Resolution of relative `media` and `reference` paths against the
+//! contract's `base_uri`, or a per-token base URI that overrides it.
+
+use crate::events::{ContractEvent, TokenBaseUriUpdateLog, UriResolutionUpdateLog};
+use crate::rbac::Role;
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{near, require, serde_json};
+use near_sdk_contract_tools::{
+    nft::{Nep171Controller, Nep177Controller, Token, TokenId, nep171::LoadTokenMetadata},
+    owner::Owner,
+    standard::nep297::Event,
+};
+use std::{collections::HashMap, error::Error};
+
+/// Whether `media` and `reference` must be absolute URIs or paths relative to
+/// the base URI.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum MediaPaths {
+    /// Either.
+    #[default]
+    Any,
+    Absolute,
+    Relative,
+}
+
+impl MediaPaths {
+    pub(crate) fn allows(self, uri: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Absolute => is_absolute_uri(uri),
+            Self::Relative => !is_absolute_uri(uri),
+        }
+    }
+}
+
+/// Resolves the relative paths of the standard views' output, when turned on
+/// by the owner.
+pub struct ResolvedUris;
+
+impl LoadTokenMetadata<MyNftContract> for ResolvedUris {
+    fn load(
+        contract: &MyNftContract,
+        token_id: &TokenId,
+        metadata: &mut HashMap<String, serde_json::Value>,
+    ) -> Result<(), Box<dyn Error>> {
+        if contract.resolve_uris_in_views {
+            contract.resolve_uris(token_id, metadata);
+        }
+
+        Ok(())
+    }
+}
+
+#[near]
+impl MyNftContract {
+    /// Turns resolution of relative paths in `nft_token`, `nft_tokens` and
+    /// the other standard views on or off. `nft_token_resolved` always
+    /// resolves them.
+    pub fn set_resolve_uris_in_views(&mut self, enabled: bool) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_resolve_uris_in_views(enabled);
+    }
+
+    pub fn resolve_uris_in_views(&self) -> bool {
+        self.resolve_uris_in_views
+    }
+
+    /// Sets the base URI the relative paths of `token_id` are resolved
+    /// against instead of the contract's `base_uri`, or removes it with
+    /// `None`.
+    pub fn nft_set_token_base_uri(&mut self, token_id: TokenId, base_uri: Option<String>) {
+        Self::require_owner_or_role(Role::MetadataEditor);
+        require!(
+            self.token_owner(&token_id).is_some(),
+            "Token does not exist"
+        );
+
+        if let Some(base_uri) = &base_uri {
+            require!(is_absolute_uri(base_uri), "Base URI must be absolute");
+            self.token_base_uris
+                .insert(token_id.clone(), base_uri.clone());
+        } else {
+            self.token_base_uris.remove(&token_id);
+        }
+
+        ContractEvent::TokenBaseUriUpdate(vec![TokenBaseUriUpdateLog { token_id, base_uri }])
+            .emit();
+    }
+
+    /// The base URI the relative paths of `token_id` are resolved against.
+    pub fn nft_token_base_uri(&self, token_id: TokenId) -> Option<String> {
+        self.base_uri_for(&token_id)
+    }
+
+    /// Same as `nft_token`, with relative `media` and `reference` paths
+    /// joined with the token's base URI.
+    pub fn nft_token_resolved(&self, token_id: TokenId) -> Option<Token> {
+        let mut token = self.load_token(&token_id)?;
+        self.resolve_uris(&token_id, &mut token.extensions_metadata);
+        Some(token)
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_resolve_uris_in_views(&mut self, enabled: bool) {
+        self.resolve_uris_in_views = enabled;
+
+        ContractEvent::UriResolutionUpdate(vec![UriResolutionUpdateLog { enabled }]).emit();
+    }
+
+    pub(crate) fn clear_token_base_uri(&mut self, token_id: &TokenId) {
+        self.token_base_uris.remove(token_id);
+    }
+
+    fn base_uri_for(&self, token_id: &TokenId) -> Option<String> {
+        self.token_base_uris
+            .get(token_id)
+            .cloned()
+            .or_else(|| self.contract_metadata().base_uri)
+    }
+
+    /// Joins the relative `media` and `reference` paths in a token's view
+    /// output with its base URI, if it has one.
+    fn resolve_uris(&self, token_id: &TokenId, metadata: &mut HashMap<String, serde_json::Value>) {
+        let Some(base_uri) = self.base_uri_for(token_id) else {
+            return;
+        };
+        let Some(token_metadata) = metadata
+            .get_mut("metadata")
+            .and_then(serde_json::Value::as_object_mut)
+        else {
+            return;
+        };
+
+        for field in ["media", "reference"] {
+            if let Some(serde_json::Value::String(path)) = token_metadata.get_mut(field) {
+                if !is_absolute_uri(path) {
+                    *path = join_uri(&base_uri, path);
+                }
+            }
+        }
+    }
+}
+
+/// Schemes whose URIs are not followed by `//`.
+const OPAQUE_SCHEMES: &[&str] = &["data"];
+
+/// Whether `uri` starts with a scheme and `//`, e.g. `https://` or
+/// `ipfs://`, or is a `data:` URI. A path such as `example.com:8080/a.png`
+/// is relative.
+fn is_absolute_uri(uri: &str) -> bool {
+    let Some((scheme, rest)) = uri.split_once(':') else {
+        return false;
+    };
+
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && (rest.starts_with("//")
+            || OPAQUE_SCHEMES
+                .iter()
+                .any(|opaque| scheme.eq_ignore_ascii_case(opaque)))
+}
+
+fn join_uri(base_uri: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base_uri.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
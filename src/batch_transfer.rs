@@ -0,0 +1,288 @@
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{
+    AccountId, Gas, PromiseOrValue, PromiseResult, assert_one_yocto, env, ext_contract, near,
+    require, serde_json,
+};
+use near_sdk_contract_tools::{
+    hook::Hook,
+    nft::{
+        Nep171Controller, Nep171Transfer, TokenId,
+        nep171::{
+            CheckExternalTransfer, Nep171TransferAuthorization,
+            error::Nep171TransferError,
+            event::{Nep171Event, NftTransferLog},
+        },
+    },
+    standard::nep297::Event,
+};
+
+/// Most gas a single function call can be given.
+const MAX_PREPAID_GAS: Gas = Gas::from_tgas(300);
+
+/// Upper bound on the gas of a batch call apart from its entries: the call
+/// itself, argument parsing, the batch event and scheduling the receiver call.
+const GAS_FOR_BATCH_TRANSFER: Gas = Gas::from_tgas(30);
+
+/// Upper bound on the gas of one batch entry, including its hooks and their
+/// events. `tests/batch_transfer.rs` measures a full batch against it.
+const GAS_PER_BATCH_TRANSFER: Gas = Gas::from_tgas(8);
+
+/// Minimum gas left for the receiver's `nft_on_batch_transfer`.
+const GAS_FOR_NFT_ON_BATCH_TRANSFER: Gas = Gas::from_tgas(10);
+
+/// Gas for `nft_resolve_batch_transfer`, on top of the gas for reverting each
+/// token.
+const GAS_FOR_RESOLVE_BATCH_TRANSFER: Gas = Gas::from_tgas(5);
+
+/// Gas for reverting one token in `nft_resolve_batch_transfer`.
+const GAS_FOR_RESOLVE_BATCH_TRANSFER_PER_TOKEN: Gas = Gas::from_tgas(4);
+
+/// Maximum number of entries in one batch: as many as fit in a call with
+/// the most gas once `nft_batch_transfer_call` has set aside gas for the
+/// receiver and for reverting every token.
+const MAX_BATCH_TRANSFER_SIZE: usize = ((MAX_PREPAID_GAS.as_gas()
+    - GAS_FOR_BATCH_TRANSFER.as_gas()
+    - GAS_FOR_NFT_ON_BATCH_TRANSFER.as_gas()
+    - GAS_FOR_RESOLVE_BATCH_TRANSFER.as_gas())
+    / (GAS_PER_BATCH_TRANSFER.as_gas() + GAS_FOR_RESOLVE_BATCH_TRANSFER_PER_TOKEN.as_gas()))
+    as usize;
+
+/// Receiver side of `nft_batch_transfer_call`.
+#[ext_contract(ext_nft_batch_receiver)]
+pub trait NftBatchReceiver {
+    /// Called once with all tokens of a batch. Returns, for each token in
+    /// `token_ids`, `true` if it should be returned to its previous owner.
+    fn nft_on_batch_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<bool>>;
+}
+
+/// One transfer of a batch, with the same arguments as `nft_transfer`.
+#[derive(Clone, Debug)]
+#[near(serializers = [json])]
+pub struct BatchTransferEntry {
+    pub receiver_id: AccountId,
+    pub token_id: TokenId,
+    pub approval_id: Option<u32>,
+    pub memo: Option<String>,
+}
+
+#[near]
+impl MyNftContract {
+    /// Performs several `nft_transfer`s for one yoctoNEAR. Each entry is
+    /// checked and hooked like a single transfer; if any of them fails, none
+    /// of them happen. Emits one `nft_transfer` event for the whole batch.
+    #[payable]
+    pub fn nft_batch_transfer(&mut self, transfers: Vec<BatchTransferEntry>) {
+        assert_one_yocto();
+        require_batch_size(transfers.len());
+
+        let sender_id = env::predecessor_account_id();
+
+        let logs = transfers
+            .into_iter()
+            .map(|entry| self.batch_transfer_one(&sender_id, entry))
+            .collect();
+
+        Nep171Event::NftTransfer(logs).emit();
+    }
+
+    /// Transfers `token_ids` to `receiver_id` like `nft_batch_transfer` and
+    /// calls `nft_on_batch_transfer` on the receiver once for all of them.
+    /// `approval_ids`, if given, has one entry per token. Resolves to one
+    /// flag per token, `true` if the receiver kept it.
+    #[payable]
+    pub fn nft_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        approval_ids: Option<Vec<Option<u32>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<bool>> {
+        assert_one_yocto();
+        require_batch_size(token_ids.len());
+
+        let approval_ids = approval_ids.unwrap_or_else(|| vec![None; token_ids.len()]);
+        require!(
+            approval_ids.len() == token_ids.len(),
+            "Number of approval IDs does not match the number of tokens"
+        );
+
+        let sender_id = env::predecessor_account_id();
+
+        let logs = token_ids
+            .iter()
+            .zip(approval_ids)
+            .map(|(token_id, approval_id)| {
+                self.batch_transfer_one(
+                    &sender_id,
+                    BatchTransferEntry {
+                        receiver_id: receiver_id.clone(),
+                        token_id: token_id.clone(),
+                        approval_id,
+                        memo: memo.clone(),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let previous_owner_ids = logs
+            .iter()
+            .map(|log| log.old_owner_id.clone().into_owned())
+            .collect::<Vec<_>>();
+
+        Nep171Event::NftTransfer(logs).emit();
+
+        let resolve_gas = GAS_FOR_RESOLVE_BATCH_TRANSFER.saturating_add(
+            GAS_FOR_RESOLVE_BATCH_TRANSFER_PER_TOKEN.saturating_mul(token_ids.len() as u64),
+        );
+        require!(
+            env::prepaid_gas().saturating_sub(env::used_gas())
+                >= GAS_FOR_NFT_ON_BATCH_TRANSFER.saturating_add(resolve_gas),
+            "More gas is required"
+        );
+
+        ext_nft_batch_receiver::ext(receiver_id.clone())
+            .with_static_gas(GAS_FOR_NFT_ON_BATCH_TRANSFER)
+            .nft_on_batch_transfer(
+                sender_id,
+                previous_owner_ids.clone(),
+                token_ids.clone(),
+                msg,
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(resolve_gas)
+                    .with_unused_gas_weight(0)
+                    .nft_resolve_batch_transfer(previous_owner_ids, receiver_id, token_ids),
+            )
+            .into()
+    }
+
+    /// Returns the tokens the receiver asked to return, or all of them if
+    /// `nft_on_batch_transfer` failed or did not answer for every token.
+    /// Emits one `nft_transfer` event for the returned tokens.
+    #[private]
+    pub fn nft_resolve_batch_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<bool> {
+        let should_return = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<Vec<bool>>(&value)
+                .ok()
+                .filter(|should_return| should_return.len() == token_ids.len()),
+            PromiseResult::Failed => None,
+        }
+        .unwrap_or_else(|| vec![true; token_ids.len()]);
+
+        let mut logs = vec![];
+
+        let kept = token_ids
+            .into_iter()
+            .zip(previous_owner_ids)
+            .zip(should_return)
+            .map(|((token_id, previous_owner_id), should_return)| {
+                if !should_return {
+                    return true;
+                }
+
+                let transfer = Nep171Transfer {
+                    token_id,
+                    authorization: Nep171TransferAuthorization::Owner,
+                    sender_id: receiver_id.clone().into(),
+                    receiver_id: previous_owner_id.into(),
+                    memo: None,
+                    msg: None,
+                    revert: true,
+                };
+
+                // The receiver may have moved the token on already, in which
+                // case it stays where it is.
+                match self.transfer_without_event(&transfer) {
+                    Ok(log) => {
+                        logs.push(log);
+                        false
+                    }
+                    Err(_) => true,
+                }
+            })
+            .collect();
+
+        if !logs.is_empty() {
+            Nep171Event::NftTransfer(logs).emit();
+        }
+
+        kept
+    }
+}
+
+impl MyNftContract {
+    fn batch_transfer_one(
+        &mut self,
+        sender_id: &AccountId,
+        entry: BatchTransferEntry,
+    ) -> NftTransferLog<'static> {
+        let transfer = Nep171Transfer {
+            token_id: entry.token_id,
+            authorization: entry
+                .approval_id
+                .map(Nep171TransferAuthorization::ApprovalId)
+                .unwrap_or(Nep171TransferAuthorization::Owner),
+            sender_id: sender_id.clone().into(),
+            receiver_id: entry.receiver_id.into(),
+            memo: entry.memo.map(Into::into),
+            msg: None,
+            revert: false,
+        };
+
+        self.transfer_without_event(&transfer)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()))
+    }
+
+    /// Runs a transfer through the same check and hooks as
+    /// `Nep171Controller::external_transfer`, but returns its event log
+    /// instead of emitting it.
+    fn transfer_without_event(
+        &mut self,
+        transfer: &Nep171Transfer,
+    ) -> Result<NftTransferLog<'static>, Nep171TransferError> {
+        let owner_id = <Self as Nep171Controller>::CheckExternalTransfer::check_external_transfer(
+            self, transfer,
+        )?;
+
+        <Self as Nep171Controller>::TransferHook::hook(self, transfer, |contract| {
+            contract.transfer_unchecked(
+                std::slice::from_ref(&transfer.token_id),
+                &transfer.receiver_id,
+            );
+        });
+
+        Ok(NftTransferLog {
+            authorized_id: match transfer.authorization {
+                Nep171TransferAuthorization::ApprovalId(_) => {
+                    Some(transfer.sender_id.clone().into_owned().into())
+                }
+                Nep171TransferAuthorization::Owner => None,
+            },
+            old_owner_id: owner_id.into(),
+            new_owner_id: transfer.receiver_id.clone().into_owned().into(),
+            token_ids: vec![transfer.token_id.clone().into()],
+            memo: transfer.memo.clone().map(|memo| memo.into_owned().into()),
+        })
+    }
+}
+
+fn require_batch_size(len: usize) {
+    require!(len > 0, "Batch is empty");
+    require!(
+        len <= MAX_BATCH_TRANSFER_SIZE,
+        format!("Batch cannot have more than {MAX_BATCH_TRANSFER_SIZE} transfers")
+    );
+}
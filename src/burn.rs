@@ -1,14 +1,55 @@
+use crate::rbac::Role;
 use crate::{MyNftContract, MyNftContractExt};
-use near_sdk::{assert_one_yocto, env, near};
-use near_sdk_contract_tools::nft::{Nep177Controller, TokenId};
+use near_sdk::{AccountId, assert_one_yocto, env, near, require};
+use near_sdk_contract_tools::{
+    nft::{Nep171Controller, Nep177Controller, TokenId},
+    pause::Pause,
+};
 
 #[near]
 impl MyNftContract {
     #[payable]
     pub fn nft_burn(&mut self, token_id: TokenId) {
         assert_one_yocto();
+        Self::require_unpaused();
 
-        Nep177Controller::burn_with_metadata(self, &token_id, &env::predecessor_account_id())
+        self.internal_burn(&token_id, &env::predecessor_account_id());
+    }
+
+    /// Burns a token regardless of who owns it. Also works while the
+    /// contract is paused.
+    pub fn nft_moderation_burn(&mut self, token_id: TokenId) {
+        Self::require_owner_or_role(Role::Moderator);
+
+        let owner_id = self
+            .token_owner(&token_id)
+            .unwrap_or_else(|| env::panic_str("Token does not exist"));
+
+        self.internal_burn(&token_id, &owner_id);
+    }
+}
+
+impl MyNftContract {
+    /// Burns the token and clears everything recorded about it. Returns the
+    /// storage freed by the token's own records, which its owner paid for.
+    pub(crate) fn internal_burn(&mut self, token_id: &TokenId, owner_id: &AccountId) -> u64 {
+        require!(
+            !self.is_nested(token_id) && !self.has_nested_tokens(token_id),
+            "Tokens that are nested or hold nested tokens cannot be burned"
+        );
+
+        let storage_usage_before = env::storage_usage();
+        Nep177Controller::burn_with_metadata(self, token_id, owner_id)
             .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+        let freed = storage_usage_before.saturating_sub(env::storage_usage());
+
+        self.clear_transfer_history(token_id);
+        self.clear_token_lockup(token_id);
+        self.clear_token_traits(token_id);
+        self.clear_token_base_uri(token_id);
+        self.clear_burned_token_source(token_id);
+        self.release_wrapped_token(token_id, owner_id);
+
+        freed
     }
 }
@@ -0,0 +1,104 @@
+//! Host-side helpers for the NEP-177 `media_hash` and `reference_hash`
+//! fields: the base64-encoded SHA-256 hash of the file a URI points at. Not
+//! compiled into the contract.
+
+use near_sdk::{
+    base64::{Engine, engine::general_purpose::STANDARD},
+    serde_json::Value,
+};
+use near_sdk_contract_tools::nft::{Token, TokenMetadata};
+use sha2::{Digest, Sha256};
+use std::{fmt, fs, io, path::Path};
+
+/// Hashes `bytes` the way NEP-177 expects.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    STANDARD.encode(Sha256::digest(bytes))
+}
+
+/// Hashes the contents of the file at `path`.
+pub fn hash_file(path: impl AsRef<Path>) -> io::Result<String> {
+    Ok(hash_bytes(&fs::read(path)?))
+}
+
+/// Sets `media` to `uri`, where the file at `path` has been uploaded, and
+/// `media_hash` to its hash.
+pub fn with_media_file(
+    metadata: TokenMetadata,
+    uri: impl Into<String>,
+    path: impl AsRef<Path>,
+) -> io::Result<TokenMetadata> {
+    Ok(metadata.media(uri).media_hash(hash_file(path)?))
+}
+
+/// Sets `reference` to `uri`, where the file at `path` has been uploaded, and
+/// `reference_hash` to its hash.
+pub fn with_reference_file(
+    metadata: TokenMetadata,
+    uri: impl Into<String>,
+    path: impl AsRef<Path>,
+) -> io::Result<TokenMetadata> {
+    Ok(metadata.reference(uri).reference_hash(hash_file(path)?))
+}
+
+/// Why a token's hash does not match a local file.
+#[derive(Debug)]
+pub enum VerifyError {
+    Io(io::Error),
+    /// The token has no hash, or no metadata, to compare with.
+    MissingHash,
+    Mismatch {
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Cannot read file: {e}"),
+            Self::MissingHash => write!(f, "Token has no hash to verify"),
+            Self::Mismatch { expected, actual } => {
+                write!(f, "Token hash {expected} does not match file hash {actual}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<io::Error> for VerifyError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Checks the `media_hash` of a token returned by `nft_token` against the
+/// file at `path`.
+pub fn verify_media_hash(token: &Token, path: impl AsRef<Path>) -> Result<(), VerifyError> {
+    verify_hash(token, "media_hash", path.as_ref())
+}
+
+/// Checks the `reference_hash` of a token returned by `nft_token` against the
+/// file at `path`.
+pub fn verify_reference_hash(token: &Token, path: impl AsRef<Path>) -> Result<(), VerifyError> {
+    verify_hash(token, "reference_hash", path.as_ref())
+}
+
+fn verify_hash(token: &Token, field: &str, path: &Path) -> Result<(), VerifyError> {
+    let expected = token
+        .extensions_metadata
+        .get("metadata")
+        .and_then(|metadata| metadata.get(field))
+        .and_then(Value::as_str)
+        .ok_or(VerifyError::MissingHash)?;
+    let actual = hash_file(path)?;
+
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(VerifyError::Mismatch {
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
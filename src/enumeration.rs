@@ -0,0 +1,93 @@
+//! NEP-181 enumeration. Mirrors near-sdk-contract-tools' generated
+//! implementation, except that `nft_tokens_for_owner` can also list the
+//! tokens nested under the account's tokens, and that the tokens the contract
+//! account holds for nesting are not listed as its own.
+
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, env, json_types::U128, near};
+use near_sdk_contract_tools::nft::{
+    Nep171Controller, Nep181Controller, Nep181ControllerInternal, Token, TokenId,
+};
+
+impl Nep181ControllerInternal for MyNftContract {}
+
+#[near]
+impl MyNftContract {
+    pub fn nft_total_supply(&self) -> U128 {
+        Nep181Controller::total_enumerated_tokens(self).into()
+    }
+
+    pub fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u32>) -> Vec<Token> {
+        Nep181Controller::with_tokens(self, |tokens| {
+            let it = tokens
+                .iter()
+                .skip(from_index.map_or(0, |i| i.0 as usize))
+                .map(|token_id| self.load_enumerated_token(&token_id));
+
+            if let Some(limit) = limit {
+                it.take(limit as usize).collect()
+            } else {
+                it.collect()
+            }
+        })
+    }
+
+    pub fn nft_supply_for_owner(&self, account_id: AccountId) -> U128 {
+        (self.tokens_held_by(&account_id).len() as u128).into()
+    }
+
+    /// With `include_nested`, every token nested under the account's tokens
+    /// is listed directly after its parent.
+    pub fn nft_tokens_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+        include_nested: Option<bool>,
+    ) -> Vec<Token> {
+        let include_nested = include_nested.unwrap_or(false);
+
+        let it = self
+            .tokens_held_by(&account_id)
+            .into_iter()
+            .flat_map(|token_id| {
+                let descendants = if include_nested {
+                    self.token_descendants(&token_id)
+                } else {
+                    vec![]
+                };
+                std::iter::once(token_id).chain(descendants)
+            })
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .map(|token_id| self.load_enumerated_token(&token_id));
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+}
+
+impl MyNftContract {
+    /// The tokens `account_id` owns, leaving out the nested tokens the
+    /// contract account holds on behalf of their root owners.
+    fn tokens_held_by(&self, account_id: &AccountId) -> Vec<TokenId> {
+        let holds_nested = *account_id == env::current_account_id();
+
+        Nep181Controller::with_tokens_for_owner(self, account_id, |tokens| {
+            tokens
+                .iter()
+                .filter(|token_id| !holds_nested || !self.is_nested(token_id))
+                .collect()
+        })
+    }
+
+    fn load_enumerated_token(&self, token_id: &TokenId) -> Token {
+        self.load_token(token_id).unwrap_or_else(|| {
+            env::panic_str(&format!(
+                "Inconsistent state: Token `{token_id}` is in the enumeration set but its metadata could not be loaded."
+            ))
+        })
+    }
+}
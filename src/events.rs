@@ -0,0 +1,340 @@
+//! NEP-297 events for this contract's own features.
+//!
+//! Mints, transfers, burns and metadata updates are reported with the
+//! standard NEP-171 events that near-sdk-contract-tools emits; the events here
+//! cover everything else that changes state.
+
+use crate::access_list::AccessMode;
+use crate::airdrop::AirdropSkip;
+use crate::fees::FeePolicy;
+use crate::lockup::LockupPolicy;
+use crate::metadata_policy::MetadataPolicy;
+use crate::multisig::{MultisigCall, MultisigConfig};
+use crate::rbac::Role;
+use crate::receiver::{ExternalToken, ReceiverPolicy};
+use crate::sponsorship::SponsorshipCaps;
+use crate::storage_management::UnregisterPolicy;
+use crate::timelock::AdminAction;
+use near_sdk::{
+    AccountId, NearToken,
+    json_types::U64,
+    serde::{Deserialize, Serialize},
+};
+use near_sdk_contract_tools::{
+    Nep297,
+    nft::{StorageBalanceBounds, TokenId},
+};
+
+#[derive(Nep297, Serialize, Deserialize, Debug, Clone)]
+#[nep297(
+    standard = "my_nft_contract",
+    version = "1.0.0",
+    rename_all = "snake_case"
+)]
+#[serde(crate = "near_sdk::serde", untagged)]
+pub enum ContractEvent {
+    /// Storage was deposited for an account on its behalf, e.g. when a mint
+    /// or airdrop registers the recipient.
+    StorageDeposit(Vec<StorageDepositLog>),
+    /// A transfer changed the contract's storage usage.
+    TransferStorage(Vec<TransferStorageLog>),
+    NftApprove(Vec<NftApproveLog>),
+    NftRevoke(Vec<NftRevokeLog>),
+    NftRevokeAll(Vec<NftRevokeAllLog>),
+    ExternalDeposit(Vec<ExternalTokenLog>),
+    ExternalWithdraw(Vec<ExternalTokenLog>),
+    NftWrap(Vec<NftWrapLog>),
+    NftUnwrap(Vec<NftWrapLog>),
+    /// A received token was kept as burned and a replacement minted for it.
+    NftBurnToMint(Vec<NftWrapLog>),
+    NftRedeem(Vec<NftRedeemLog>),
+    ReceiverPolicyUpdate(Vec<ReceiverPolicyUpdateLog>),
+    RedeemRecipeAdd(Vec<RedeemRecipeLog>),
+    RedeemRecipeRemove(Vec<RedeemRecipeLog>),
+    AirdropSkip(Vec<AirdropSkip>),
+    AirdropJobCreate(Vec<AirdropJobLog>),
+    AirdropJobSeal(Vec<AirdropJobLog>),
+    AirdropJobProgress(Vec<AirdropJobProgressLog>),
+    HistoryRetentionUpdate(Vec<HistoryRetentionUpdateLog>),
+    AccessModeUpdate(Vec<AccessModeUpdateLog>),
+    AccountsBlock(Vec<AccountListLog>),
+    AccountsUnblock(Vec<AccountListLog>),
+    AccountsAllow(Vec<AccountListLog>),
+    AccountsDisallow(Vec<AccountListLog>),
+    CollectionLockupUpdate(Vec<CollectionLockupUpdateLog>),
+    /// The owner replaced or lifted a token's lockup.
+    TokenLockupOverride(Vec<TokenLockupOverrideLog>),
+    FeePolicyUpdate(Vec<FeePolicyUpdateLog>),
+    FeeWithdraw(Vec<FeeWithdrawLog>),
+    TimelockDelayUpdate(Vec<TimelockDelayUpdateLog>),
+    AdminActionQueue(Vec<AdminActionQueueLog>),
+    AdminActionCancel(Vec<AdminActionLog>),
+    AdminActionExecute(Vec<AdminActionLog>),
+    MultisigConfigUpdate(Vec<MultisigConfigUpdateLog>),
+    MultisigPropose(Vec<MultisigProposeLog>),
+    MultisigConfirm(Vec<MultisigConfirmLog>),
+    MultisigCancel(Vec<MultisigProposalLog>),
+    MultisigExecute(Vec<MultisigProposalLog>),
+    /// The call of an executed proposal failed, and the proposal can be
+    /// executed again.
+    MultisigExecuteFailure(Vec<MultisigProposalLog>),
+    RoleGrant(Vec<RoleLog>),
+    RoleRevoke(Vec<RoleLog>),
+    StorageBoundsUpdate(Vec<StorageBoundsUpdateLog>),
+    UnregisterPolicyUpdate(Vec<UnregisterPolicyUpdateLog>),
+    SponsorPoolFund(Vec<SponsorPoolLog>),
+    SponsorPoolWithdraw(Vec<SponsorPoolLog>),
+    SponsorshipCapsUpdate(Vec<SponsorshipCapsUpdateLog>),
+    /// An unregistered token receiver was registered from the sponsorship
+    /// pool.
+    StorageSponsor(Vec<StorageDepositLog>),
+    /// The sponsored storage of an account that unregistered went back to
+    /// the sponsorship pool.
+    StorageSponsorReclaim(Vec<StorageDepositLog>),
+    MetadataPolicyUpdate(Vec<MetadataPolicyUpdateLog>),
+    SvgTemplateUpdate(Vec<SvgTemplateUpdateLog>),
+    UriResolutionUpdate(Vec<UriResolutionUpdateLog>),
+    TokenBaseUriUpdate(Vec<TokenBaseUriUpdateLog>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageDepositLog {
+    pub account_id: AccountId,
+    pub amount: NearToken,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferStorageLog {
+    pub token_id: TokenId,
+    /// Account charged for the storage the transfer added.
+    pub receiver_id: AccountId,
+    /// Change in storage usage, in bytes.
+    pub storage_delta: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftApproveLog {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+    pub account_id: AccountId,
+    pub approval_id: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftRevokeLog {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+    pub account_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftRevokeAllLog {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExternalTokenLog {
+    pub account_id: AccountId,
+    pub token: ExternalToken,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftWrapLog {
+    pub owner_id: AccountId,
+    pub token_id: TokenId,
+    pub source: ExternalToken,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftRedeemLog {
+    pub owner_id: AccountId,
+    pub recipe_id: String,
+    pub burned_token_ids: Vec<TokenId>,
+    pub token_id: TokenId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReceiverPolicyUpdateLog {
+    pub policy: ReceiverPolicy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RedeemRecipeLog {
+    pub recipe_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AirdropJobLog {
+    pub job_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AirdropJobProgressLog {
+    pub job_id: u64,
+    pub cursor: u64,
+    pub recipients: u64,
+    pub minted: u64,
+    pub skipped: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HistoryRetentionUpdateLog {
+    pub retention: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccessModeUpdateLog {
+    pub mode: AccessMode,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountListLog {
+    pub account_ids: Vec<AccountId>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionLockupUpdateLog {
+    pub policy: LockupPolicy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenLockupOverrideLog {
+    pub token_id: TokenId,
+    pub policy: Option<LockupPolicy>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeePolicyUpdateLog {
+    pub policy: Option<FeePolicy>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeeWithdrawLog {
+    pub account_id: AccountId,
+    pub amount: NearToken,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TimelockDelayUpdateLog {
+    pub delay_seconds: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdminActionQueueLog {
+    pub action_id: u64,
+    pub action: AdminAction,
+    pub executable_at: U64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdminActionLog {
+    pub action_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigConfigUpdateLog {
+    pub config: Option<MultisigConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigProposeLog {
+    pub proposal_id: u64,
+    pub proposer_id: AccountId,
+    pub call: MultisigCall,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigConfirmLog {
+    pub proposal_id: u64,
+    pub account_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigProposalLog {
+    pub proposal_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleLog {
+    pub account_id: AccountId,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBoundsUpdateLog {
+    pub bounds: StorageBalanceBounds,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UnregisterPolicyUpdateLog {
+    pub policy: UnregisterPolicy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SponsorPoolLog {
+    pub amount: NearToken,
+    /// Pool balance afterwards.
+    pub balance: NearToken,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SponsorshipCapsUpdateLog {
+    pub caps: SponsorshipCaps,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MetadataPolicyUpdateLog {
+    pub policy: MetadataPolicy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SvgTemplateUpdateLog {
+    pub template: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UriResolutionUpdateLog {
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenBaseUriUpdateLog {
+    pub token_id: TokenId,
+    pub base_uri: Option<String>,
+}
@@ -0,0 +1,270 @@
+use crate::events::{ContractEvent, FeePolicyUpdateLog, FeeWithdrawLog};
+use crate::rbac::Role;
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, Gas, NearToken, Promise, assert_one_yocto, env, near, require};
+use near_sdk_contract_tools::{
+    nft::{Nep171Controller, Nep171Transfer, TokenId, nep171::Nep171TransferAuthorization},
+    owner::{Owner, OwnerInternal},
+    standard::nep297::Event,
+};
+
+const GAS_FOR_RESOLVE_FEE_WITHDRAW: Gas = Gas::from_tgas(5);
+
+/// Fee charged on transfers, paid by the sender through `nft_transfer_paid`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct FeePolicy {
+    /// Account the fees are credited to.
+    pub beneficiary_id: AccountId,
+    /// Charged on every transfer.
+    pub flat: NearToken,
+    /// Charged on the price declared to `nft_transfer_paid`, in basis points.
+    /// The sender declares the price, and can declare 0 to pay only the flat
+    /// fee, so this only holds for senders that report the real price, e.g.
+    /// marketplaces.
+    pub basis_points: u16,
+    /// Transfers made through an approval held by one of these accounts,
+    /// e.g. marketplaces that collect fees themselves, are free.
+    pub exempt_approved_accounts: Vec<AccountId>,
+    /// Transfers out of the collection owner's account, i.e. primary
+    /// distribution rather than secondary transfers, are free.
+    pub exempt_owner_transfers: bool,
+}
+
+impl FeePolicy {
+    fn fee_for(&self, price: NearToken) -> NearToken {
+        let price = price.as_yoctonear();
+        let basis_points = u128::from(self.basis_points);
+        // Dividing first loses nothing for prices too large to multiply.
+        let percentage = price
+            .checked_mul(basis_points)
+            .map_or_else(|| price / 10_000 * basis_points, |p| p / 10_000);
+
+        self.flat
+            .saturating_add(NearToken::from_yoctonear(percentage))
+    }
+}
+
+/// Deposit attached to the current call that is available for transfer fees.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FeePayment {
+    budget: NearToken,
+    price: NearToken,
+}
+
+#[near]
+impl MyNftContract {
+    /// Sets the transfer fee, or removes it with `None`. Fees already
+    /// collected stay with their beneficiaries.
+    pub fn set_fee_policy(&mut self, policy: Option<FeePolicy>) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_fee_policy(policy);
+    }
+
+    pub fn fee_policy(&self) -> Option<FeePolicy> {
+        self.fee_policy.clone()
+    }
+
+    /// Returns the fee a transfer of `token_id` by the token's owner would
+    /// cost at `price`.
+    pub fn nft_transfer_fee(&self, token_id: TokenId, price: Option<NearToken>) -> NearToken {
+        let Some(owner_id) = self.token_owner(&token_id) else {
+            return NearToken::from_yoctonear(0);
+        };
+
+        self.transfer_fee(
+            &Nep171Transfer {
+                token_id,
+                authorization: Nep171TransferAuthorization::Owner,
+                sender_id: owner_id.clone().into(),
+                receiver_id: owner_id.clone().into(),
+                memo: None,
+                msg: None,
+                revert: false,
+            },
+            &owner_id,
+            price.unwrap_or_default(),
+        )
+    }
+
+    /// Same as `nft_transfer`, but the attached deposit pays the transfer
+    /// fee, if any. `price` is the sale price the percentage fee applies
+    /// to; it is not verified, and defaults to 0. Whatever is not needed for
+    /// the fee is refunded.
+    #[payable]
+    pub fn nft_transfer_paid(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u32>,
+        memo: Option<String>,
+        price: Option<NearToken>,
+    ) {
+        let attached = env::attached_deposit();
+        require!(
+            !attached.is_zero(),
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+
+        let sender_id = env::predecessor_account_id();
+
+        self.fee_payment = Some(FeePayment {
+            budget: attached,
+            price: price.unwrap_or_default(),
+        });
+
+        Nep171Controller::external_transfer(
+            self,
+            &Nep171Transfer {
+                token_id,
+                authorization: approval_id
+                    .map(Nep171TransferAuthorization::ApprovalId)
+                    .unwrap_or(Nep171TransferAuthorization::Owner),
+                sender_id: sender_id.clone().into(),
+                receiver_id: receiver_id.into(),
+                memo: memo.map(Into::into),
+                msg: None,
+                revert: false,
+            },
+        )
+        .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+
+        let refund = self.fee_payment.take().unwrap_or_default().budget;
+        if !refund.is_zero() {
+            Promise::new(sender_id).transfer(refund);
+        }
+    }
+
+    pub fn fee_balance_of(&self, account_id: AccountId) -> NearToken {
+        self.fee_balances
+            .get(&account_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sends the caller all fees credited to them.
+    #[payable]
+    pub fn withdraw_fees(&mut self) -> Promise {
+        assert_one_yocto();
+
+        let account_id = env::predecessor_account_id();
+
+        self.internal_withdraw_fees(account_id.clone(), account_id)
+    }
+
+    /// Sends `receiver_id` all fees credited to the contract account itself,
+    /// i.e. when the contract is the fee beneficiary.
+    #[payable]
+    pub fn withdraw_treasury_fees(&mut self, receiver_id: AccountId) -> Promise {
+        assert_one_yocto();
+        Self::require_owner_or_role(Role::Treasurer);
+
+        self.internal_withdraw_fees(env::current_account_id(), receiver_id)
+    }
+
+    /// Credits the fees back if they could not be sent.
+    #[private]
+    pub fn resolve_fee_withdraw(&mut self, account_id: AccountId, amount: NearToken) -> bool {
+        if near_sdk::is_promise_success() {
+            true
+        } else {
+            let balance = self.fee_balances.entry(account_id).or_default();
+            *balance = balance.saturating_add(amount);
+            false
+        }
+    }
+}
+
+impl MyNftContract {
+    fn internal_withdraw_fees(&mut self, account_id: AccountId, receiver_id: AccountId) -> Promise {
+        let amount = self
+            .fee_balances
+            .remove(&account_id)
+            .filter(|amount| !amount.is_zero())
+            .unwrap_or_else(|| env::panic_str("No fees to withdraw"));
+
+        ContractEvent::FeeWithdraw(vec![FeeWithdrawLog {
+            account_id: account_id.clone(),
+            amount,
+        }])
+        .emit();
+
+        Promise::new(receiver_id).transfer(amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE_FEE_WITHDRAW)
+                .resolve_fee_withdraw(account_id, amount),
+        )
+    }
+
+    pub(crate) fn internal_set_fee_policy(&mut self, policy: Option<FeePolicy>) {
+        if let Some(policy) = &policy {
+            require!(policy.basis_points <= 10_000, "Fee cannot exceed 100%");
+        }
+
+        self.fee_policy = policy.clone();
+
+        ContractEvent::FeePolicyUpdate(vec![FeePolicyUpdateLog { policy }]).emit();
+    }
+
+    /// Charges the fee for `transfer` to the deposit of the current call.
+    /// Panics if the fee is not covered, which is always the case for
+    /// transfers made through the standard methods.
+    pub(crate) fn collect_transfer_fee(&mut self, transfer: &Nep171Transfer, owner_id: &AccountId) {
+        // Nested tokens are not sold.
+        if self.moving_custody {
+            return;
+        }
+        let Some(policy) = &self.fee_policy else {
+            return;
+        };
+        let beneficiary_id = policy.beneficiary_id.clone();
+
+        let mut payment = self.fee_payment.unwrap_or_default();
+        let fee = self.transfer_fee(transfer, owner_id, payment.price);
+        if fee.is_zero() {
+            return;
+        }
+
+        require!(
+            payment.budget >= fee,
+            format!(
+                "Transfer fee of {} yoctoNEAR must be attached to nft_transfer_paid",
+                fee.as_yoctonear()
+            )
+        );
+        payment.budget = payment.budget.saturating_sub(fee);
+        self.fee_payment = Some(payment);
+
+        let balance = self.fee_balances.entry(beneficiary_id).or_default();
+        *balance = balance.saturating_add(fee);
+    }
+
+    fn transfer_fee(
+        &self,
+        transfer: &Nep171Transfer,
+        owner_id: &AccountId,
+        price: NearToken,
+    ) -> NearToken {
+        let Some(policy) = &self.fee_policy else {
+            return NearToken::from_yoctonear(0);
+        };
+
+        let approved_exempt = matches!(
+            transfer.authorization,
+            Nep171TransferAuthorization::ApprovalId(_)
+        ) && policy
+            .exempt_approved_accounts
+            .iter()
+            .any(|account_id| **account_id == *transfer.sender_id);
+        let owner_exempt =
+            policy.exempt_owner_transfers && Self::slot_owner().read().as_ref() == Some(owner_id);
+
+        if approved_exempt || owner_exempt {
+            NearToken::from_yoctonear(0)
+        } else {
+            policy.fee_for(price)
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::events::{ContractEvent, HistoryRetentionUpdateLog};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{
+    AccountId, env,
+    json_types::{U64, U128},
+    near,
+};
+use near_sdk_contract_tools::{
+    nft::{Nep171Transfer, TokenId},
+    owner::Owner,
+    standard::nep297::Event,
+};
+
+/// One past transfer of a token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct TransferRecord {
+    pub from: AccountId,
+    pub to: AccountId,
+    /// Block timestamp of the transfer, in nanoseconds.
+    pub timestamp: U64,
+    pub memo: Option<String>,
+    /// Whether the transfer was made by `nft_transfer_call`.
+    pub transfer_call: bool,
+}
+
+#[near]
+impl MyNftContract {
+    /// Sets how many of its most recent transfers are kept per token. `0`
+    /// turns history off. The contract pays for history storage; lowering
+    /// the retention trims each token's history on its next transfer.
+    pub fn set_history_retention(&mut self, retention: u32) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_history_retention(retention);
+    }
+
+    pub fn history_retention(&self) -> u32 {
+        self.history_retention
+    }
+
+    /// Lists the retained transfers of `token_id`, oldest first.
+    pub fn nft_history(
+        &self,
+        token_id: TokenId,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<TransferRecord> {
+        let Some(history) = self.token_history.get(&token_id) else {
+            return vec![];
+        };
+
+        let it = history
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .cloned();
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_history_retention(&mut self, retention: u32) {
+        self.history_retention = retention;
+
+        ContractEvent::HistoryRetentionUpdate(vec![HistoryRetentionUpdateLog { retention }]).emit();
+    }
+
+    /// Appends `transfer` from `from` to the token's history, dropping the
+    /// oldest records beyond the retention length.
+    pub(crate) fn record_transfer(&mut self, transfer: &Nep171Transfer, from: AccountId) {
+        let retention = self.history_retention as usize;
+        if retention == 0 {
+            self.token_history.remove(&transfer.token_id);
+            return;
+        }
+
+        let history = self
+            .token_history
+            .entry(transfer.token_id.clone())
+            .or_default();
+
+        history.push(TransferRecord {
+            from,
+            to: transfer.receiver_id.clone().into_owned(),
+            timestamp: env::block_timestamp().into(),
+            memo: transfer.memo.clone().map(|memo| memo.into_owned()),
+            transfer_call: transfer.msg.is_some(),
+        });
+
+        if history.len() > retention {
+            history.drain(..history.len() - retention);
+        }
+    }
+
+    pub(crate) fn clear_transfer_history(&mut self, token_id: &TokenId) {
+        self.token_history.remove(token_id);
+    }
+}
@@ -1,30 +1,238 @@
+use crate::access_list::AccessMode;
+use crate::airdrop::{AirdropJob, AirdropRecipient, AirdropSkip};
+use crate::approval_hook::ApprovalHook;
+use crate::base_uri::ResolvedUris;
+use crate::fees::{FeePayment, FeePolicy};
+use crate::history::TransferRecord;
+use crate::lockup::{LockupPolicy, TokenLockup};
+use crate::metadata_policy::MetadataPolicy;
+use crate::multisig::{MultisigConfig, MultisigProposal};
+use crate::rbac::Role;
+use crate::receiver::{ExternalToken, ReceiverPolicy};
+use crate::redeem::RedeemRecipe;
+use crate::sponsorship::{SponsorHook, SponsorshipCaps};
+use crate::storage::DEFAULT_STORAGE_BALANCE_BOUNDS;
+use crate::storage_management::{StorageAccountingHook, UnregisterPolicy};
+use crate::svg::SvgMedia;
+use crate::timelock::QueuedAdminAction;
+use crate::traits::{TokenTrait, TokenTraits};
 use crate::transfer_hook::TransferHook;
-use near_sdk::{AccountId, NearToken, PanicOnDefault, near};
-use near_sdk_contract_tools::{Owner, nft::*, owner::*};
+use near_sdk::{
+    AccountId, BorshStorageKey, NearToken, PanicOnDefault, near,
+    store::{IterableMap, IterableSet, LookupMap},
+};
+use near_sdk_contract_tools::{Pause, Rbac, nft::*, owner::*};
 
+mod access_list;
+mod airdrop;
+mod approval_hook;
+mod base_uri;
+mod batch_transfer;
 mod burn;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod content_hash;
+mod enumeration;
+pub mod events;
+mod fees;
+mod history;
+mod lockup;
+mod metadata_policy;
 mod mint;
+mod multisig;
+mod nesting;
+mod pause;
+mod rbac;
+mod receiver;
+mod redeem;
+mod sponsorship;
+mod storage;
+mod storage_management;
+mod svg;
+mod timelock;
+mod traits;
 mod transfer_hook;
+mod wrap;
 
-#[derive(PanicOnDefault, Owner, NonFungibleToken)]
-#[non_fungible_token(transfer_hook = "TransferHook")]
+pub use batch_transfer::{NftBatchReceiver, ext_nft_batch_receiver};
+
+#[derive(BorshStorageKey)]
+#[near]
+enum StorageKey {
+    TokenParents,
+    TokenChildren,
+    ExternalDeposits,
+    ExternalDepositsByAccount,
+    RedeemRecipes,
+    WrappedTokens,
+    AirdropJobs,
+    AirdropJobRecipients,
+    AirdropJobSkips,
+    TokenHistory,
+    BlockedAccounts,
+    AllowedAccounts,
+    TokenLockups,
+    FeeBalances,
+    AdminActions,
+    MultisigProposals,
+    SponsoredAccounts,
+    TokenTraits,
+    TraitTokens,
+    TraitTokenSet((String, String)),
+    TraitCounts,
+    TraitValues,
+    TraitValueSet(String),
+    TokenBaseUris,
+    BurnedExternalTokens,
+    SponsorshipReceived,
+}
+
+// The parts of `NonFungibleToken`, so that NEP-145 and NEP-181 can be
+// implemented by hand in `storage_management` and `enumeration`.
+#[derive(PanicOnDefault, Nep171, Nep177, Nep178, Pause, Rbac)]
+#[nep171(
+    all_hooks = "(SponsorHook, (StorageAccountingHook, (TokenApprovals, TokenEnumeration)))",
+    transfer_hook = "TransferHook",
+    check_external_transfer = "TokenApprovals",
+    token_data = "(TokenMetadata, (TokenApprovals, (TokenTraits, (SvgMedia, ResolvedUris))))"
+)]
+#[nep178(
+    approve_hook = "ApprovalHook",
+    revoke_hook = "ApprovalHook",
+    revoke_all_hook = "ApprovalHook"
+)]
+#[rbac(roles = "Role")]
 #[near(contract_state)]
-pub struct MyNftContract {}
+pub struct MyNftContract {
+    token_parents: LookupMap<TokenId, TokenId>,
+    token_children: LookupMap<TokenId, Vec<TokenId>>,
+    receiver_policy: ReceiverPolicy,
+    external_deposits: LookupMap<ExternalToken, AccountId>,
+    external_deposits_by_account: LookupMap<AccountId, Vec<ExternalToken>>,
+    redeem_recipes: IterableMap<String, RedeemRecipe>,
+    wrapped_tokens: LookupMap<TokenId, ExternalToken>,
+    burned_external_tokens: LookupMap<TokenId, ExternalToken>,
+    airdrop_jobs: IterableMap<u64, AirdropJob>,
+    airdrop_job_recipients: LookupMap<(u64, u64), AirdropRecipient>,
+    airdrop_job_skips: LookupMap<(u64, u64), AirdropSkip>,
+    next_airdrop_job_id: u64,
+    token_history: LookupMap<TokenId, Vec<TransferRecord>>,
+    history_retention: u32,
+    access_mode: AccessMode,
+    blocked_accounts: IterableSet<AccountId>,
+    allowed_accounts: IterableSet<AccountId>,
+    collection_lockup: LockupPolicy,
+    token_lockups: LookupMap<TokenId, TokenLockup>,
+    fee_policy: Option<FeePolicy>,
+    fee_balances: LookupMap<AccountId, NearToken>,
+    /// Fee deposit of the current call, never persisted.
+    #[borsh(skip)]
+    fee_payment: Option<FeePayment>,
+    timelock_delay_seconds: u32,
+    admin_actions: IterableMap<u64, QueuedAdminAction>,
+    next_admin_action_id: u64,
+    owner_proposed_at: Option<u64>,
+    multisig: Option<MultisigConfig>,
+    multisig_proposals: IterableMap<u64, MultisigProposal>,
+    next_multisig_proposal_id: u64,
+    token_storage_usage: u64,
+    unregister_policy: UnregisterPolicy,
+    /// Set while a force-unregistered account's tokens go to the sink, never
+    /// persisted.
+    #[borsh(skip)]
+    force_unregistering: bool,
+    /// Set while a token is moved in or out of the contract account's
+    /// custody by nesting, never persisted.
+    #[borsh(skip)]
+    moving_custody: bool,
+    sponsor_pool: NearToken,
+    sponsorship_caps: SponsorshipCaps,
+    sponsored_total: NearToken,
+    sponsored_accounts: IterableMap<AccountId, NearToken>,
+    /// Storage sponsored to each account over all its registrations.
+    sponsorship_received: LookupMap<AccountId, NearToken>,
+    metadata_policy: MetadataPolicy,
+    token_traits: LookupMap<TokenId, Vec<TokenTrait>>,
+    trait_tokens: LookupMap<(String, String), IterableSet<TokenId>>,
+    trait_counts: LookupMap<(String, String), u32>,
+    trait_values: LookupMap<String, IterableSet<String>>,
+    svg_template: Option<String>,
+    resolve_uris_in_views: bool,
+    token_base_uris: LookupMap<TokenId, String>,
+}
 
 #[near]
 impl MyNftContract {
+    /// Pass the contract account as `owner_id` together with a `multisig` to
+    /// have the multisig administer the contract instead of a single owner.
+    /// `storage_balance_bounds` defaults to a 0.007 NEAR minimum and a
+    /// 0.021 NEAR maximum.
     #[init]
-    pub fn new(owner_id: AccountId, metadata: ContractMetadata) -> Self {
-        let mut contract = Self {};
+    pub fn new(
+        owner_id: AccountId,
+        metadata: ContractMetadata,
+        multisig: Option<MultisigConfig>,
+        storage_balance_bounds: Option<StorageBalanceBounds>,
+    ) -> Self {
+        let mut contract = Self {
+            token_parents: LookupMap::new(StorageKey::TokenParents),
+            token_children: LookupMap::new(StorageKey::TokenChildren),
+            receiver_policy: ReceiverPolicy::default(),
+            external_deposits: LookupMap::new(StorageKey::ExternalDeposits),
+            external_deposits_by_account: LookupMap::new(StorageKey::ExternalDepositsByAccount),
+            redeem_recipes: IterableMap::new(StorageKey::RedeemRecipes),
+            wrapped_tokens: LookupMap::new(StorageKey::WrappedTokens),
+            burned_external_tokens: LookupMap::new(StorageKey::BurnedExternalTokens),
+            airdrop_jobs: IterableMap::new(StorageKey::AirdropJobs),
+            airdrop_job_recipients: LookupMap::new(StorageKey::AirdropJobRecipients),
+            airdrop_job_skips: LookupMap::new(StorageKey::AirdropJobSkips),
+            next_airdrop_job_id: 0,
+            token_history: LookupMap::new(StorageKey::TokenHistory),
+            history_retention: 0,
+            access_mode: AccessMode::default(),
+            blocked_accounts: IterableSet::new(StorageKey::BlockedAccounts),
+            allowed_accounts: IterableSet::new(StorageKey::AllowedAccounts),
+            collection_lockup: LockupPolicy::default(),
+            token_lockups: LookupMap::new(StorageKey::TokenLockups),
+            fee_policy: None,
+            fee_balances: LookupMap::new(StorageKey::FeeBalances),
+            fee_payment: None,
+            timelock_delay_seconds: 0,
+            admin_actions: IterableMap::new(StorageKey::AdminActions),
+            next_admin_action_id: 0,
+            owner_proposed_at: None,
+            multisig: None,
+            multisig_proposals: IterableMap::new(StorageKey::MultisigProposals),
+            next_multisig_proposal_id: 0,
+            token_storage_usage: 0,
+            unregister_policy: UnregisterPolicy::default(),
+            force_unregistering: false,
+            moving_custody: false,
+            sponsor_pool: NearToken::from_yoctonear(0),
+            sponsorship_caps: SponsorshipCaps::default(),
+            sponsored_total: NearToken::from_yoctonear(0),
+            sponsored_accounts: IterableMap::new(StorageKey::SponsoredAccounts),
+            sponsorship_received: LookupMap::new(StorageKey::SponsorshipReceived),
+            metadata_policy: MetadataPolicy::default(),
+            token_traits: LookupMap::new(StorageKey::TokenTraits),
+            trait_tokens: LookupMap::new(StorageKey::TraitTokens),
+            trait_counts: LookupMap::new(StorageKey::TraitCounts),
+            trait_values: LookupMap::new(StorageKey::TraitValues),
+            svg_template: None,
+            resolve_uris_in_views: false,
+            token_base_uris: LookupMap::new(StorageKey::TokenBaseUris),
+        };
 
         Owner::init(&mut contract, &owner_id);
 
+        if multisig.is_some() {
+            contract.internal_set_multisig_config(multisig);
+        }
+
         contract.set_contract_metadata(&metadata);
 
-        contract.set_storage_balance_bounds(&StorageBalanceBounds {
-            min: NearToken::from_yoctonear(7000000000000000000000),
-            max: Some(NearToken::from_yoctonear(21000000000000000000000)),
-        });
+        contract.internal_set_storage_bounds(
+            storage_balance_bounds.unwrap_or(DEFAULT_STORAGE_BALANCE_BOUNDS),
+        );
 
         contract
     }
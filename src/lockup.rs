@@ -0,0 +1,172 @@
+use crate::events::{CollectionLockupUpdateLog, ContractEvent, TokenLockupOverrideLog};
+use crate::rbac::Role;
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{env, json_types::U64, near, require};
+use near_sdk_contract_tools::{
+    nft::{Nep171Controller, TokenId},
+    owner::Owner,
+    standard::nep297::Event,
+};
+
+/// Restrictions on when a token may be transferred.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct LockupPolicy {
+    /// No transfers before this time, in milliseconds since the Unix epoch.
+    pub locked_until: Option<U64>,
+    /// Minimum time between a token's mint or last transfer and its next
+    /// transfer, in seconds.
+    pub cooldown_seconds: Option<u32>,
+}
+
+impl LockupPolicy {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.locked_until.is_none() && self.cooldown_seconds.is_none()
+    }
+}
+
+/// The lockup of one token, recorded when it is minted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct TokenLockup {
+    pub policy: LockupPolicy,
+    /// When the token was minted or last transferred, in milliseconds.
+    pub last_moved_at: U64,
+}
+
+impl TokenLockup {
+    /// Earliest time the token may be transferred, in milliseconds.
+    fn transferable_at(&self) -> u64 {
+        let after_cooldown = self.policy.cooldown_seconds.map_or(0, |cooldown| {
+            self.last_moved_at
+                .0
+                .saturating_add(u64::from(cooldown) * 1000)
+        });
+
+        self.policy
+            .locked_until
+            .map_or(0, |locked_until| locked_until.0)
+            .max(after_cooldown)
+    }
+}
+
+#[near]
+impl MyNftContract {
+    /// Sets the lockup recorded for tokens minted from now on. Tokens that
+    /// already exist keep theirs.
+    pub fn set_collection_lockup(&mut self, policy: LockupPolicy) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_collection_lockup(policy);
+    }
+
+    pub fn collection_lockup(&self) -> LockupPolicy {
+        self.collection_lockup.clone()
+    }
+
+    /// Replaces the lockup of an existing token, or lifts it with `None`.
+    pub fn set_token_lockup(&mut self, token_id: TokenId, policy: Option<LockupPolicy>) {
+        Self::require_owner_or_role(Role::Pauser);
+
+        self.internal_set_token_lockup(token_id, policy);
+    }
+
+    pub fn token_lockup(&self, token_id: TokenId) -> Option<TokenLockup> {
+        self.token_lockups.get(&token_id).cloned()
+    }
+
+    /// Returns when `token_id` next becomes transferable, in milliseconds.
+    /// That may be in the past. Returns `None` if the token does not exist.
+    pub fn nft_transferable_at(&self, token_id: TokenId) -> Option<U64> {
+        self.token_owner(&token_id)?;
+
+        Some(
+            self.token_lockups
+                .get(&token_id)
+                .map_or(0, TokenLockup::transferable_at)
+                .into(),
+        )
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_collection_lockup(&mut self, policy: LockupPolicy) {
+        self.collection_lockup = policy.clone();
+
+        ContractEvent::CollectionLockupUpdate(vec![CollectionLockupUpdateLog { policy }]).emit();
+    }
+
+    pub(crate) fn internal_set_token_lockup(
+        &mut self,
+        token_id: TokenId,
+        policy: Option<LockupPolicy>,
+    ) {
+        require!(
+            self.token_owner(&token_id).is_some(),
+            "Token does not exist"
+        );
+
+        match policy.clone().filter(|policy| !policy.is_empty()) {
+            Some(policy) => {
+                let last_moved_at = self
+                    .token_lockups
+                    .get(&token_id)
+                    .map_or(env::block_timestamp_ms().into(), |lockup| {
+                        lockup.last_moved_at
+                    });
+                self.token_lockups.insert(
+                    token_id.clone(),
+                    TokenLockup {
+                        policy,
+                        last_moved_at,
+                    },
+                );
+            }
+            None => {
+                self.token_lockups.remove(&token_id);
+            }
+        }
+
+        ContractEvent::TokenLockupOverride(vec![TokenLockupOverrideLog { token_id, policy }])
+            .emit();
+    }
+
+    /// Records the collection lockup for a newly minted token.
+    pub(crate) fn record_mint_lockup(&mut self, token_id: &TokenId) {
+        if self.collection_lockup.is_empty() {
+            return;
+        }
+
+        self.token_lockups.insert(
+            token_id.clone(),
+            TokenLockup {
+                policy: self.collection_lockup.clone(),
+                last_moved_at: env::block_timestamp_ms().into(),
+            },
+        );
+    }
+
+    pub(crate) fn require_unlocked(&self, token_id: &TokenId) {
+        let Some(lockup) = self.token_lockups.get(token_id) else {
+            return;
+        };
+
+        let transferable_at = lockup.transferable_at();
+        require!(
+            env::block_timestamp_ms() >= transferable_at,
+            format!("Token is locked until {transferable_at}")
+        );
+    }
+
+    /// Restarts the token's cooldown after a transfer.
+    pub(crate) fn record_lockup_transfer(&mut self, token_id: &TokenId) {
+        if let Some(lockup) = self.token_lockups.get_mut(token_id) {
+            lockup.last_moved_at = env::block_timestamp_ms().into();
+        }
+    }
+
+    pub(crate) fn clear_token_lockup(&mut self, token_id: &TokenId) {
+        self.token_lockups.remove(token_id);
+    }
+}
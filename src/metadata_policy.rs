@@ -0,0 +1,244 @@
+use crate::base_uri::MediaPaths;
+use crate::events::{ContractEvent, MetadataPolicyUpdateLog};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{
+    base64::{Engine, engine::general_purpose::STANDARD},
+    env, near, serde_json,
+};
+use near_sdk_contract_tools::{nft::TokenMetadata, owner::Owner, standard::nep297::Event};
+use std::{collections::BTreeMap, fmt};
+
+/// Length in bytes of the SHA-256 hashes in `media_hash` and
+/// `reference_hash`.
+const HASH_LEN: usize = 32;
+
+/// A field of `TokenMetadata`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataField {
+    Title,
+    Description,
+    Media,
+    MediaHash,
+    Copies,
+    IssuedAt,
+    ExpiresAt,
+    StartsAt,
+    UpdatedAt,
+    Extra,
+    Reference,
+    ReferenceHash,
+}
+
+impl MetadataField {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Description => "description",
+            Self::Media => "media",
+            Self::MediaHash => "media_hash",
+            Self::Copies => "copies",
+            Self::IssuedAt => "issued_at",
+            Self::ExpiresAt => "expires_at",
+            Self::StartsAt => "starts_at",
+            Self::UpdatedAt => "updated_at",
+            Self::Extra => "extra",
+            Self::Reference => "reference",
+            Self::ReferenceHash => "reference_hash",
+        }
+    }
+
+    fn is_set(self, metadata: &TokenMetadata) -> bool {
+        match self {
+            Self::Copies => metadata.copies.is_some(),
+            Self::IssuedAt => metadata.issued_at.is_some(),
+            Self::ExpiresAt => metadata.expires_at.is_some(),
+            Self::StartsAt => metadata.starts_at.is_some(),
+            Self::UpdatedAt => metadata.updated_at.is_some(),
+            _ => self.text(metadata).is_some(),
+        }
+    }
+
+    /// The value of a string field.
+    fn text(self, metadata: &TokenMetadata) -> Option<&str> {
+        match self {
+            Self::Title => metadata.title.as_deref(),
+            Self::Description => metadata.description.as_deref(),
+            Self::Media => metadata.media.as_deref(),
+            Self::MediaHash => metadata.media_hash.as_deref(),
+            Self::Extra => metadata.extra.as_deref(),
+            Self::Reference => metadata.reference.as_deref(),
+            Self::ReferenceHash => metadata.reference_hash.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Rules the metadata of minted and updated tokens must follow. The default
+/// accepts any metadata.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct MetadataPolicy {
+    /// Fields that must be set. A required string field must not be empty.
+    pub required_fields: Vec<MetadataField>,
+    /// Maximum length in bytes of string fields.
+    pub max_lengths: BTreeMap<MetadataField, u32>,
+    /// Schemes `media` and `reference` may use, e.g. `https` or `ipfs`.
+    /// Empty allows any value. Not checked when `media_paths` requires
+    /// relative paths, which have no scheme.
+    pub url_schemes: Vec<String>,
+    /// Whether `media_hash` and `reference_hash` must be the base64 encoding
+    /// of a 32-byte hash.
+    pub check_hashes: bool,
+    /// Whether `extra` must be valid JSON.
+    pub extra_json: bool,
+    /// Whether `media` and `reference` must be absolute URIs or paths
+    /// relative to the base URI.
+    #[serde(default)]
+    pub media_paths: MediaPaths,
+}
+
+/// Why token metadata was rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MetadataError {
+    Missing(MetadataField),
+    TooLong(MetadataField, u32),
+    UrlScheme(MetadataField, Vec<String>),
+    Hash(MetadataField),
+    ExtraJson,
+    Path(MetadataField, MediaPaths),
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(field) => write!(f, "Metadata `{}` is required", field.name()),
+            Self::TooLong(field, max_length) => write!(
+                f,
+                "Metadata `{}` is longer than {max_length} bytes",
+                field.name()
+            ),
+            Self::UrlScheme(field, schemes) => write!(
+                f,
+                "Metadata `{}` must be a URL with one of the schemes: {}",
+                field.name(),
+                schemes.join(", ")
+            ),
+            Self::Hash(field) => write!(
+                f,
+                "Metadata `{}` must be the base64 encoding of {HASH_LEN} bytes",
+                field.name()
+            ),
+            Self::ExtraJson => write!(f, "Metadata `extra` must be valid JSON"),
+            Self::Path(field, MediaPaths::Relative) => write!(
+                f,
+                "Metadata `{}` must be a path relative to the base URI",
+                field.name()
+            ),
+            Self::Path(field, _) => {
+                write!(f, "Metadata `{}` must be an absolute URI", field.name())
+            }
+        }
+    }
+}
+
+impl MetadataPolicy {
+    pub(crate) fn check(&self, metadata: &TokenMetadata) -> Result<(), MetadataError> {
+        for &field in &self.required_fields {
+            if !field.is_set(metadata) || field.text(metadata).is_some_and(str::is_empty) {
+                return Err(MetadataError::Missing(field));
+            }
+        }
+
+        for (&field, &max_length) in &self.max_lengths {
+            if field
+                .text(metadata)
+                .is_some_and(|text| text.len() > max_length as usize)
+            {
+                return Err(MetadataError::TooLong(field, max_length));
+            }
+        }
+
+        for field in [MetadataField::Media, MetadataField::Reference] {
+            if field
+                .text(metadata)
+                .is_some_and(|uri| !self.media_paths.allows(uri))
+            {
+                return Err(MetadataError::Path(field, self.media_paths));
+            }
+        }
+
+        if !self.url_schemes.is_empty() && self.media_paths != MediaPaths::Relative {
+            for field in [MetadataField::Media, MetadataField::Reference] {
+                if field
+                    .text(metadata)
+                    .is_some_and(|url| !self.url_schemes.iter().any(|s| s == url_scheme(url)))
+                {
+                    return Err(MetadataError::UrlScheme(field, self.url_schemes.clone()));
+                }
+            }
+        }
+
+        if self.check_hashes {
+            for field in [MetadataField::MediaHash, MetadataField::ReferenceHash] {
+                if field.text(metadata).is_some_and(|hash| {
+                    STANDARD
+                        .decode(hash)
+                        .map_or(true, |bytes| bytes.len() != HASH_LEN)
+                }) {
+                    return Err(MetadataError::Hash(field));
+                }
+            }
+        }
+
+        if self.extra_json
+            && metadata
+                .extra
+                .as_deref()
+                .is_some_and(|extra| serde_json::from_str::<serde_json::Value>(extra).is_err())
+        {
+            return Err(MetadataError::ExtraJson);
+        }
+
+        Ok(())
+    }
+}
+
+/// The scheme of `url`, i.e. what comes before `://`, or an empty string.
+fn url_scheme(url: &str) -> &str {
+    match url.split_once("://") {
+        Some((scheme, rest)) if !rest.is_empty() => scheme,
+        _ => "",
+    }
+}
+
+#[near]
+impl MyNftContract {
+    /// Sets the rules for the metadata of tokens minted or updated from now
+    /// on. Existing tokens are not checked.
+    pub fn set_metadata_policy(&mut self, policy: MetadataPolicy) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_metadata_policy(policy);
+    }
+
+    pub fn metadata_policy(&self) -> MetadataPolicy {
+        self.metadata_policy.clone()
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_metadata_policy(&mut self, policy: MetadataPolicy) {
+        self.metadata_policy = policy.clone();
+
+        ContractEvent::MetadataPolicyUpdate(vec![MetadataPolicyUpdateLog { policy }]).emit();
+    }
+
+    pub(crate) fn require_valid_metadata(&self, metadata: &TokenMetadata) {
+        if let Err(e) = self.metadata_policy.check(metadata) {
+            env::panic_str(&e.to_string());
+        }
+    }
+}
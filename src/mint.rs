@@ -1,8 +1,13 @@
+use crate::events::{ContractEvent, StorageDepositLog};
+use crate::rbac::Role;
+use crate::traits::TokenTrait;
 use crate::{MyNftContract, MyNftContractExt};
-use near_sdk::{AccountId, env, log, near};
+use near_sdk::{AccountId, Promise, env, near};
 use near_sdk_contract_tools::{
     ft::Nep145,
-    nft::{Nep177Controller, TokenId, TokenMetadata},
+    nft::{Nep145Controller, Nep177Controller, TokenId, TokenMetadata},
+    pause::Pause,
+    standard::nep297::Event,
 };
 
 #[near]
@@ -13,29 +18,93 @@ impl MyNftContract {
         token_id: TokenId,
         metadata: TokenMetadata,
         owner_id: Option<AccountId>,
+        traits: Option<Vec<TokenTrait>>,
     ) {
+        Self::require_owner_or_role(Role::Minter);
+
+        self.internal_mint(
+            &token_id,
+            &metadata,
+            &owner_id.unwrap_or(env::predecessor_account_id()),
+            &traits.unwrap_or_default(),
+        );
+    }
+
+    /// Replaces the metadata of an existing token.
+    pub fn nft_set_token_metadata(&mut self, token_id: TokenId, metadata: TokenMetadata) {
+        Self::require_owner_or_role(Role::MetadataEditor);
+        self.require_valid_metadata(&metadata);
+
+        Nep177Controller::set_token_metadata(self, &token_id, &metadata)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_mint(
+        &mut self,
+        token_id: &TokenId,
+        metadata: &TokenMetadata,
+        owner_id: &AccountId,
+        traits: &[TokenTrait],
+    ) {
+        Self::require_unpaused();
+        self.require_account_access(owner_id);
+
         // Check account's storage balance and deposit if necessary
         let storage_balance_bounds = self.storage_balance_bounds();
-        log!("Storage balance bounds: {:?}", storage_balance_bounds);
 
         let storage_balance = self
-            .storage_balance_of(owner_id.clone().unwrap_or(env::predecessor_account_id()))
+            .storage_balance_of(owner_id.clone())
             .unwrap_or_default();
-        log!("Storage balance: {:?}", storage_balance);
-        if storage_balance.total < storage_balance_bounds.min {
-            // Deposit storage if necessary
-            self.storage_deposit(
-                Some(owner_id.clone().unwrap_or(env::predecessor_account_id())),
-                None,
-            );
+        let attached = env::attached_deposit();
+        // Without a deposit, an unregistered owner can still be registered
+        // from the sponsorship pool.
+        if storage_balance.total < storage_balance_bounds.min && !attached.is_zero() {
+            // Deposit the attached deposit, without going over the maximum
+            // for accounts registered under a lower minimum.
+            let amount = storage_balance_bounds.max.map_or(attached, |max| {
+                attached.min(max.saturating_sub(storage_balance.total))
+            });
+
+            self.deposit_to_storage_account(owner_id, amount)
+                .unwrap_or_else(|e| env::panic_str(&format!("Storage deposit error: {e}")));
+
+            let refund = attached.saturating_sub(amount);
+            if !refund.is_zero() {
+                Promise::new(env::predecessor_account_id()).transfer(refund);
+            }
+
+            ContractEvent::StorageDeposit(vec![StorageDepositLog {
+                account_id: owner_id.clone(),
+                amount,
+            }])
+            .emit();
         }
 
-        Nep177Controller::mint_with_metadata(
-            self,
-            &token_id,
-            &owner_id.unwrap_or(env::predecessor_account_id()),
-            &metadata,
-        )
-        .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+        self.mint_token(token_id, owner_id, metadata, traits);
+    }
+
+    /// Mints a token whose owner's storage is already taken care of.
+    pub(crate) fn mint_token(
+        &mut self,
+        token_id: &TokenId,
+        owner_id: &AccountId,
+        metadata: &TokenMetadata,
+        traits: &[TokenTrait],
+    ) {
+        self.require_valid_metadata(metadata);
+
+        let storage_usage_before = env::storage_usage();
+
+        Nep177Controller::mint_with_metadata(self, token_id, owner_id, metadata)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+
+        // Only the token itself counts towards the measurement: traits are
+        // charged separately and lockups are paid by the contract.
+        self.record_token_storage_usage(storage_usage_before);
+
+        self.record_mint_lockup(token_id);
+        self.record_token_traits(token_id, owner_id, traits);
     }
 }
@@ -0,0 +1,334 @@
+//! In-contract multisig. Admins propose calls to this contract's own
+//! methods, confirm them, and once enough admins have confirmed, any admin
+//! can execute them. Calls are made by the contract account itself, so with
+//! the contract account as its own owner, the multisig takes the place of a
+//! plain owner account for every owner-only method. Proposals expire after
+//! a week, and are only removed once their call succeeds.
+
+use crate::events::{
+    ContractEvent, MultisigConfigUpdateLog, MultisigConfirmLog, MultisigProposalLog,
+    MultisigProposeLog,
+};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{
+    AccountId, Gas, GasWeight, NearToken, Promise, env,
+    json_types::{Base64VecU8, U64, U128},
+    near, require,
+};
+use near_sdk_contract_tools::standard::nep297::Event;
+
+/// How long a proposal can be confirmed and executed, in milliseconds.
+const PROPOSAL_LIFETIME_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+const GAS_FOR_RESOLVE_EXECUTE: Gas = Gas::from_tgas(5);
+
+/// Methods proposals can call: those of the owner and the roles, which the
+/// contract account can call as the owner. Everything else, in particular
+/// the private callbacks, is out of reach of the multisig.
+const PROPOSABLE_METHODS: &[&str] = &[
+    "own_propose_owner",
+    "own_accept_owner",
+    "own_renounce_owner",
+    "set_multisig_config",
+    "grant_role",
+    "revoke_role",
+    "set_timelock_delay",
+    "queue_admin_action",
+    "cancel_admin_action",
+    "execute_admin_action",
+    "pause_contract",
+    "unpause_contract",
+    "nft_mint",
+    "nft_set_token_metadata",
+    "nft_set_token_base_uri",
+    "nft_moderation_burn",
+    "nft_airdrop",
+    "create_airdrop_job",
+    "add_airdrop_recipients",
+    "seal_airdrop_job",
+    "set_access_mode",
+    "block_accounts",
+    "unblock_accounts",
+    "allow_accounts",
+    "disallow_accounts",
+    "set_fee_policy",
+    "withdraw_treasury_fees",
+    "set_collection_lockup",
+    "set_token_lockup",
+    "set_receiver_policy",
+    "set_storage_bounds",
+    "reset_token_storage_usage",
+    "set_unregister_policy",
+    "fund_sponsor_pool",
+    "set_sponsorship_caps",
+    "withdraw_sponsor_pool",
+    "set_metadata_policy",
+    "set_svg_template",
+    "set_resolve_uris_in_views",
+    "set_history_retention",
+    "add_redeem_recipe",
+    "remove_redeem_recipe",
+];
+
+/// Admin accounts and the number of them that have to confirm a proposal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct MultisigConfig {
+    pub admins: Vec<AccountId>,
+    pub threshold: u32,
+}
+
+impl MultisigConfig {
+    fn validate(&self) {
+        require!(self.threshold > 0, "Multisig threshold must be at least 1");
+        require!(
+            self.threshold as usize <= self.admins.len(),
+            "Multisig threshold cannot exceed the number of admins"
+        );
+        require!(
+            self.admins
+                .iter()
+                .enumerate()
+                .all(|(i, admin)| !self.admins[..i].contains(admin)),
+            "Multisig admins must be unique"
+        );
+    }
+}
+
+/// A call to one of this contract's methods, made by the contract itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct MultisigCall {
+    pub method_name: String,
+    /// JSON arguments of the call.
+    pub args: Base64VecU8,
+    /// Paid from the contract balance, e.g. 1 yoctoNEAR for methods that
+    /// require it.
+    pub deposit: NearToken,
+}
+
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh, json])]
+pub struct MultisigProposal {
+    pub proposer_id: AccountId,
+    pub call: MultisigCall,
+    pub confirmations: Vec<AccountId>,
+    /// Time after which the proposal can no longer be confirmed or
+    /// executed, in milliseconds since the Unix epoch.
+    pub expires_at: U64,
+    /// Whether the call has been made and its result is not in yet.
+    pub executing: bool,
+}
+
+impl MultisigProposal {
+    fn require_open(&self) {
+        require!(
+            env::block_timestamp_ms() < self.expires_at.0,
+            "Proposal has expired"
+        );
+        require!(!self.executing, "Proposal is being executed");
+    }
+}
+
+#[near]
+impl MyNftContract {
+    pub fn multisig_config(&self) -> Option<MultisigConfig> {
+        self.multisig.clone()
+    }
+
+    /// Replaces the admins and threshold, or removes the multisig with
+    /// `None`. Only callable through a multisig proposal (or by the
+    /// contract account itself), and timelocked like the owner's setters.
+    #[private]
+    pub fn set_multisig_config(&mut self, config: Option<MultisigConfig>) {
+        self.require_not_timelocked();
+
+        self.internal_set_multisig_config(config);
+    }
+
+    /// Proposes `call` and confirms it for the proposer. Returns the
+    /// proposal ID.
+    pub fn multisig_propose(&mut self, call: MultisigCall) -> u64 {
+        let proposer_id = env::predecessor_account_id();
+        self.require_multisig_admin(&proposer_id);
+        require!(
+            PROPOSABLE_METHODS.contains(&call.method_name.as_str()),
+            format!(
+                "Method `{}` cannot be called through the multisig",
+                call.method_name
+            )
+        );
+
+        let proposal_id = self.next_multisig_proposal_id;
+        self.next_multisig_proposal_id += 1;
+
+        self.multisig_proposals.insert(
+            proposal_id,
+            MultisigProposal {
+                proposer_id: proposer_id.clone(),
+                call: call.clone(),
+                confirmations: vec![proposer_id.clone()],
+                expires_at: env::block_timestamp_ms()
+                    .saturating_add(PROPOSAL_LIFETIME_MS)
+                    .into(),
+                executing: false,
+            },
+        );
+
+        ContractEvent::MultisigPropose(vec![MultisigProposeLog {
+            proposal_id,
+            proposer_id,
+            call,
+        }])
+        .emit();
+
+        proposal_id
+    }
+
+    pub fn multisig_confirm(&mut self, proposal_id: u64) {
+        let account_id = env::predecessor_account_id();
+        self.require_multisig_admin(&account_id);
+
+        let proposal = self
+            .multisig_proposals
+            .get_mut(&proposal_id)
+            .unwrap_or_else(|| env::panic_str("Proposal does not exist"));
+        proposal.require_open();
+        require!(
+            !proposal.confirmations.contains(&account_id),
+            "Proposal is already confirmed by this admin"
+        );
+        proposal.confirmations.push(account_id.clone());
+
+        ContractEvent::MultisigConfirm(vec![MultisigConfirmLog {
+            proposal_id,
+            account_id,
+        }])
+        .emit();
+    }
+
+    /// Withdraws a proposal. Only its proposer can do this, and anyone can
+    /// remove an expired proposal.
+    pub fn multisig_cancel(&mut self, proposal_id: u64) {
+        let proposal = self
+            .multisig_proposals
+            .remove(&proposal_id)
+            .unwrap_or_else(|| env::panic_str("Proposal does not exist"));
+        require!(!proposal.executing, "Proposal is being executed");
+        require!(
+            proposal.proposer_id == env::predecessor_account_id()
+                || env::block_timestamp_ms() >= proposal.expires_at.0,
+            "Only the proposer can cancel a proposal"
+        );
+
+        ContractEvent::MultisigCancel(vec![MultisigProposalLog { proposal_id }]).emit();
+    }
+
+    /// Makes the proposed call once enough current admins have confirmed
+    /// it. The call gets all the gas left. The proposal is removed once the
+    /// call succeeds, and can be executed again if it fails.
+    pub fn multisig_execute(&mut self, proposal_id: u64) -> Promise {
+        let account_id = env::predecessor_account_id();
+        self.require_multisig_admin(&account_id);
+
+        let threshold = self.multisig.as_ref().map_or(0, |config| config.threshold);
+        let proposal = self
+            .multisig_proposals
+            .get(&proposal_id)
+            .unwrap_or_else(|| env::panic_str("Proposal does not exist"));
+        proposal.require_open();
+        let confirmations = proposal
+            .confirmations
+            .iter()
+            .filter(|admin| self.is_multisig_admin(admin))
+            .count();
+        require!(
+            confirmations >= threshold as usize,
+            "Proposal does not have enough confirmations"
+        );
+
+        let call = proposal.call.clone();
+        if let Some(proposal) = self.multisig_proposals.get_mut(&proposal_id) {
+            proposal.executing = true;
+        }
+
+        Promise::new(env::current_account_id())
+            .function_call_weight(
+                call.method_name,
+                call.args.into(),
+                call.deposit,
+                Gas::from_gas(0),
+                GasWeight(1),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_EXECUTE)
+                    .with_unused_gas_weight(0)
+                    .multisig_resolve_execute(proposal_id),
+            )
+    }
+
+    /// Removes an executed proposal if its call succeeded, or makes it
+    /// executable again if it failed.
+    #[private]
+    pub fn multisig_resolve_execute(&mut self, proposal_id: u64) -> bool {
+        let succeeded = near_sdk::is_promise_success();
+
+        if succeeded {
+            self.multisig_proposals.remove(&proposal_id);
+            ContractEvent::MultisigExecute(vec![MultisigProposalLog { proposal_id }]).emit();
+        } else if let Some(proposal) = self.multisig_proposals.get_mut(&proposal_id) {
+            proposal.executing = false;
+            ContractEvent::MultisigExecuteFailure(vec![MultisigProposalLog { proposal_id }]).emit();
+        }
+
+        succeeded
+    }
+
+    pub fn multisig_proposal(&self, proposal_id: u64) -> Option<MultisigProposal> {
+        self.multisig_proposals.get(&proposal_id).cloned()
+    }
+
+    pub fn multisig_proposals(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<(u64, MultisigProposal)> {
+        let it = self
+            .multisig_proposals
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .map(|(proposal_id, proposal)| (*proposal_id, proposal.clone()));
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_multisig_config(&mut self, config: Option<MultisigConfig>) {
+        if let Some(config) = &config {
+            config.validate();
+        }
+
+        self.multisig = config.clone();
+
+        ContractEvent::MultisigConfigUpdate(vec![MultisigConfigUpdateLog { config }]).emit();
+    }
+
+    fn is_multisig_admin(&self, account_id: &AccountId) -> bool {
+        self.multisig
+            .as_ref()
+            .is_some_and(|config| config.admins.contains(account_id))
+    }
+
+    fn require_multisig_admin(&self, account_id: &AccountId) {
+        require!(
+            self.is_multisig_admin(account_id),
+            "Only multisig admins can do this"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, assert_one_yocto, env, near, require};
+use near_sdk_contract_tools::nft::{
+    Nep171Controller, Nep171Transfer, Token, TokenId, nep171::Nep171TransferAuthorization,
+};
+
+/// Maximum number of tokens that can be nested directly under one token.
+const MAX_CHILDREN_PER_TOKEN: usize = 16;
+
+/// Maximum number of levels in a token tree, counting the root token.
+const MAX_NESTING_DEPTH: usize = 4;
+
+#[near]
+impl MyNftContract {
+    /// Nests `token_id` under `parent_token_id`. The caller must own
+    /// `token_id` directly and own the tree `parent_token_id` belongs to.
+    /// While nested, the token is held by the contract account and follows
+    /// its root token on every transfer. Nesting is a transfer to the
+    /// contract account, so it is subject to the same checks.
+    #[payable]
+    pub fn nft_nest(&mut self, token_id: TokenId, parent_token_id: TokenId) {
+        assert_one_yocto();
+
+        let predecessor = env::predecessor_account_id();
+
+        require!(!self.is_nested(&token_id), "Token is already nested");
+
+        let owner_id = self
+            .token_owner(&token_id)
+            .unwrap_or_else(|| env::panic_str(&format!("Token `{token_id}` does not exist")));
+        require!(owner_id == predecessor, "Only the token owner can nest it");
+
+        let root_owner_id = self
+            .nft_root_owner(parent_token_id.clone())
+            .unwrap_or_else(|| {
+                env::panic_str(&format!("Token `{parent_token_id}` does not exist"))
+            });
+        require!(
+            root_owner_id == predecessor,
+            "Only the owner of the parent token can nest into it"
+        );
+
+        let ancestors = self.token_ancestors(&parent_token_id);
+        require!(
+            token_id != parent_token_id && !ancestors.contains(&token_id),
+            "Nesting would create a cycle"
+        );
+        require!(
+            ancestors.len() + 1 + self.token_tree_height(&token_id) <= MAX_NESTING_DEPTH,
+            "Maximum nesting depth exceeded"
+        );
+
+        require!(
+            self.token_children
+                .get(&parent_token_id)
+                .is_none_or(|children| children.len() < MAX_CHILDREN_PER_TOKEN),
+            "Maximum number of nested tokens exceeded"
+        );
+
+        self.move_token_custody(&token_id, &owner_id, &env::current_account_id(), "nest");
+
+        self.token_children
+            .entry(parent_token_id.clone())
+            .or_default()
+            .push(token_id.clone());
+        self.token_parents.insert(token_id, parent_token_id);
+    }
+
+    /// Detaches `token_id` from its parent and returns it to the owner of
+    /// the token tree. Tokens nested under `token_id` stay attached to it.
+    #[payable]
+    pub fn nft_unnest(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+
+        let predecessor = env::predecessor_account_id();
+
+        let parent_token_id = self
+            .token_parents
+            .remove(&token_id)
+            .unwrap_or_else(|| env::panic_str("Token is not nested"));
+
+        require!(
+            self.nft_root_owner(parent_token_id.clone()).as_ref() == Some(&predecessor),
+            "Only the owner of the token tree can unnest from it"
+        );
+
+        let children = self
+            .token_children
+            .get_mut(&parent_token_id)
+            .unwrap_or_else(|| env::panic_str("Inconsistent state: parent has no children"));
+        children.retain(|child_id| child_id != &token_id);
+        if children.is_empty() {
+            self.token_children.remove(&parent_token_id);
+        }
+
+        self.move_token_custody(
+            &token_id,
+            &env::current_account_id(),
+            &predecessor,
+            "unnest",
+        );
+    }
+
+    /// Returns the token `token_id` is directly nested under, if any.
+    pub fn nft_parent(&self, token_id: TokenId) -> Option<TokenId> {
+        self.token_parents.get(&token_id).cloned()
+    }
+
+    /// Returns the tokens nested directly under `token_id`.
+    pub fn nft_children(&self, token_id: TokenId) -> Vec<Token> {
+        self.token_children
+            .get(&token_id)
+            .map(|children| {
+                children
+                    .iter()
+                    .filter_map(|child_id| self.load_token(child_id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the owner of the token at the root of the tree `token_id`
+    /// belongs to. For tokens that are not nested, this is the token owner.
+    pub fn nft_root_owner(&self, token_id: TokenId) -> Option<AccountId> {
+        let root_token_id = self.token_ancestors(&token_id).pop().unwrap_or(token_id);
+        self.token_owner(&root_token_id)
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn is_nested(&self, token_id: &TokenId) -> bool {
+        self.token_parents.contains_key(token_id)
+    }
+
+    pub(crate) fn has_nested_tokens(&self, token_id: &TokenId) -> bool {
+        self.token_children.contains_key(token_id)
+    }
+
+    /// Parent chain of `token_id`, nearest parent first.
+    fn token_ancestors(&self, token_id: &TokenId) -> Vec<TokenId> {
+        let mut ancestors = Vec::new();
+        let mut current = token_id;
+        while let Some(parent_token_id) = self.token_parents.get(current) {
+            ancestors.push(parent_token_id.clone());
+            current = parent_token_id;
+        }
+        ancestors
+    }
+
+    /// Number of levels in the subtree rooted at `token_id`.
+    fn token_tree_height(&self, token_id: &TokenId) -> usize {
+        1 + self
+            .token_children
+            .get(token_id)
+            .map(|children| {
+                children
+                    .iter()
+                    .map(|child_id| self.token_tree_height(child_id))
+                    .max()
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default()
+    }
+
+    /// All tokens nested under `token_id` at any depth, in depth-first order.
+    pub(crate) fn token_descendants(&self, token_id: &TokenId) -> Vec<TokenId> {
+        let mut descendants = Vec::new();
+        if let Some(children) = self.token_children.get(token_id) {
+            for child_id in children {
+                descendants.push(child_id.clone());
+                descendants.extend(self.token_descendants(child_id));
+            }
+        }
+        descendants
+    }
+
+    /// Transfers `token_id` in or out of the contract account's custody with
+    /// all the transfer hooks, except that no fee is charged and the lockup
+    /// cooldown does not restart.
+    fn move_token_custody(
+        &mut self,
+        token_id: &TokenId,
+        from: &AccountId,
+        to: &AccountId,
+        memo: &str,
+    ) {
+        // The contract account nesting its own tokens keeps them.
+        if from == to {
+            return;
+        }
+
+        let transfer = Nep171Transfer::new(
+            token_id.clone(),
+            from.clone(),
+            to.clone(),
+            Nep171TransferAuthorization::Owner,
+        )
+        .memo(memo);
+
+        self.moving_custody = true;
+        Nep171Controller::external_transfer(self, &transfer)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+        self.moving_custody = false;
+    }
+}
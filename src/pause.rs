@@ -0,0 +1,23 @@
+//! Contract-wide pause. While paused, tokens cannot be minted, transferred
+//! or burned by their owners. Tokens are still returned after failed
+//! `nft_transfer_call`s, and moderators can still burn tokens.
+
+use crate::rbac::Role;
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::near;
+use near_sdk_contract_tools::pause::Pause;
+
+#[near]
+impl MyNftContract {
+    pub fn pause_contract(&mut self) {
+        Self::require_owner_or_role(Role::Pauser);
+
+        Pause::pause(self);
+    }
+
+    pub fn unpause_contract(&mut self) {
+        Self::require_owner_or_role(Role::Pauser);
+
+        Pause::unpause(self);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::events::{ContractEvent, RoleLog};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, BorshStorageKey, env, json_types::U128, near, require};
+use near_sdk_contract_tools::{
+    owner::{Owner, OwnerInternal},
+    rbac::Rbac,
+    standard::nep297::Event,
+};
+
+/// Permissions the owner can hand out. The owner can do everything a role
+/// allows without holding it.
+#[derive(BorshStorageKey, Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Mints tokens and runs airdrops.
+    Minter,
+    /// Updates token metadata.
+    MetadataEditor,
+    /// Pauses and unpauses the contract and overrides token lockups.
+    Pauser,
+    /// Burns any token and manages the block and allow lists.
+    Moderator,
+    /// Withdraws the transfer fees credited to the contract account.
+    Treasurer,
+}
+
+#[near]
+impl MyNftContract {
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_grant_role(account_id, role);
+    }
+
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_revoke_role(account_id, role);
+    }
+
+    pub fn account_has_role(&self, account_id: AccountId, role: Role) -> bool {
+        Self::has_role(&account_id, &role)
+    }
+
+    pub fn role_members(
+        &self,
+        role: Role,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<AccountId> {
+        let it = Self::iter_members_of(&role).skip(from_index.map_or(0, |i| i.0 as usize));
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+}
+
+impl MyNftContract {
+    /// Requires the predecessor to be the owner or to hold `role`.
+    pub(crate) fn require_owner_or_role(role: Role) {
+        let predecessor = env::predecessor_account_id();
+
+        require!(
+            Self::slot_owner().read().as_ref() == Some(&predecessor)
+                || Self::has_role(&predecessor, &role),
+            format!("Only the owner or a {role:?} can do this")
+        );
+    }
+
+    pub(crate) fn internal_grant_role(&mut self, account_id: AccountId, role: Role) {
+        if !Self::has_role(&account_id, &role) {
+            self.add_role(&account_id, &role);
+
+            ContractEvent::RoleGrant(vec![RoleLog { account_id, role }]).emit();
+        }
+    }
+
+    pub(crate) fn internal_revoke_role(&mut self, account_id: AccountId, role: Role) {
+        if Self::has_role(&account_id, &role) {
+            self.remove_role(&account_id, &role);
+
+            ContractEvent::RoleRevoke(vec![RoleLog { account_id, role }]).emit();
+        }
+    }
+}
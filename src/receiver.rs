@@ -0,0 +1,256 @@
+use crate::events::{ContractEvent, ExternalTokenLog, ReceiverPolicyUpdateLog};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{
+    AccountId, Gas, NearToken, Promise, PromiseOrValue, assert_one_yocto, env, log, near, require,
+};
+use near_sdk_contract_tools::{
+    nft::{Nep171Receiver, TokenId, ext_nep171},
+    owner::Owner,
+    standard::nep297::Event,
+};
+
+/// Gas attached to `nft_transfer` when returning a deposited token to its
+/// source contract.
+const GAS_FOR_EXTERNAL_NFT_TRANSFER: Gas = Gas::from_tgas(15);
+
+/// Gas attached to the callback that resolves an external withdrawal.
+const GAS_FOR_RESOLVE_EXTERNAL_WITHDRAW: Gas = Gas::from_tgas(5);
+
+/// A token held by this contract on another NEP-171 contract (or on this
+/// one, when a holder sends one of our own tokens here).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[near(serializers = [borsh, json])]
+pub struct ExternalToken {
+    pub contract_id: AccountId,
+    pub token_id: TokenId,
+}
+
+/// What a sender asks the contract to do with a received token, as given in
+/// the `msg` of `nft_transfer_call`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiverAction {
+    /// Hold the token on behalf of its previous owner, who can withdraw it
+    /// with `nft_withdraw_external`. This is the default for an empty `msg`.
+    Deposit,
+    /// Hold the token and mint a wrapped copy of it in this collection to
+    /// its previous owner. Burning the wrapped token releases the original.
+    Wrap,
+    /// Keep the token as burned, so that it can never be withdrawn, and mint
+    /// a replacement in this collection to its previous owner.
+    BurnToMint,
+}
+
+impl ReceiverAction {
+    fn from_msg(msg: &str) -> Option<Self> {
+        match msg {
+            "" | "deposit" => Some(Self::Deposit),
+            "wrap" => Some(Self::Wrap),
+            "burn_to_mint" => Some(Self::BurnToMint),
+            _ => None,
+        }
+    }
+}
+
+/// Which tokens `nft_on_transfer` accepts. The default policy rejects
+/// everything.
+#[derive(Clone, Debug, Default)]
+#[near(serializers = [borsh, json])]
+pub struct ReceiverPolicy {
+    /// Accept tokens from any contract, ignoring `accepted_contracts`.
+    pub accept_any_contract: bool,
+    /// Contracts whose tokens are accepted.
+    pub accepted_contracts: Vec<AccountId>,
+    /// Actions senders may request through `msg`.
+    pub allowed_actions: Vec<ReceiverAction>,
+}
+
+impl ReceiverPolicy {
+    fn accepts(&self, contract_id: &AccountId, action: ReceiverAction) -> bool {
+        (self.accept_any_contract || self.accepted_contracts.contains(contract_id))
+            && self.allowed_actions.contains(&action)
+    }
+}
+
+#[near]
+impl Nep171Receiver for MyNftContract {
+    /// Accepts a token according to the receiver policy. Returns `true`,
+    /// which sends the token back, when the source contract or the requested
+    /// action is not allowed.
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        let contract_id = env::predecessor_account_id();
+
+        let Some(action) = ReceiverAction::from_msg(&msg) else {
+            log!("Unsupported msg `{}`, returning token {}", msg, token_id);
+            return PromiseOrValue::Value(true);
+        };
+
+        if !self.receiver_policy.accepts(&contract_id, action) {
+            log!(
+                "Tokens from {} are not accepted for {:?}, returning token {} to {}",
+                contract_id,
+                action,
+                token_id,
+                sender_id
+            );
+            return PromiseOrValue::Value(true);
+        }
+
+        let token = ExternalToken {
+            contract_id,
+            token_id,
+        };
+
+        match action {
+            ReceiverAction::Deposit => {
+                if self.external_deposits.contains_key(&token) {
+                    log!("Token {} is already deposited", token.token_id);
+                    return PromiseOrValue::Value(true);
+                }
+
+                ContractEvent::ExternalDeposit(vec![ExternalTokenLog {
+                    account_id: previous_owner_id.clone(),
+                    token: token.clone(),
+                }])
+                .emit();
+
+                self.record_external_deposit(token, previous_owner_id);
+                PromiseOrValue::Value(false)
+            }
+            ReceiverAction::Wrap | ReceiverAction::BurnToMint => {
+                self.wrap_external_token(token, previous_owner_id, action)
+            }
+        }
+    }
+}
+
+#[near]
+impl MyNftContract {
+    /// Replaces the policy `nft_on_transfer` applies to received tokens.
+    pub fn set_receiver_policy(&mut self, policy: ReceiverPolicy) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_receiver_policy(policy);
+    }
+
+    pub fn receiver_policy(&self) -> ReceiverPolicy {
+        self.receiver_policy.clone()
+    }
+
+    /// Returns the account that deposited `token_id` from `contract_id`, if
+    /// the contract still holds it.
+    pub fn external_depositor_of(
+        &self,
+        contract_id: AccountId,
+        token_id: TokenId,
+    ) -> Option<AccountId> {
+        self.external_deposits
+            .get(&ExternalToken {
+                contract_id,
+                token_id,
+            })
+            .cloned()
+    }
+
+    /// Lists the tokens `account_id` has deposited and not yet withdrawn.
+    pub fn external_deposits_of(&self, account_id: AccountId) -> Vec<ExternalToken> {
+        self.external_deposits_by_account
+            .get(&account_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Sends a deposited token back to the account that deposited it.
+    #[payable]
+    pub fn nft_withdraw_external(&mut self, contract_id: AccountId, token_id: TokenId) -> Promise {
+        assert_one_yocto();
+
+        let predecessor = env::predecessor_account_id();
+
+        let token = ExternalToken {
+            contract_id,
+            token_id,
+        };
+
+        let depositor = self
+            .remove_external_deposit(&token)
+            .unwrap_or_else(|| env::panic_str("Token is not deposited in this contract"));
+        require!(
+            depositor == predecessor,
+            "Only the depositor can withdraw this token"
+        );
+
+        ContractEvent::ExternalWithdraw(vec![ExternalTokenLog {
+            account_id: predecessor.clone(),
+            token: token.clone(),
+        }])
+        .emit();
+
+        ext_nep171::ext(token.contract_id.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(GAS_FOR_EXTERNAL_NFT_TRANSFER)
+            .nft_transfer(
+                predecessor.clone(),
+                token.token_id.clone(),
+                None,
+                Some("withdraw".to_string()),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_EXTERNAL_WITHDRAW)
+                    .resolve_external_withdraw(token, predecessor),
+            )
+    }
+
+    /// Restores the deposit record if the token could not be sent back.
+    #[private]
+    pub fn resolve_external_withdraw(
+        &mut self,
+        token: ExternalToken,
+        depositor: AccountId,
+    ) -> bool {
+        if near_sdk::is_promise_success() {
+            true
+        } else {
+            self.record_external_deposit(token, depositor);
+            false
+        }
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_receiver_policy(&mut self, policy: ReceiverPolicy) {
+        self.receiver_policy = policy.clone();
+
+        ContractEvent::ReceiverPolicyUpdate(vec![ReceiverPolicyUpdateLog { policy }]).emit();
+    }
+
+    pub(crate) fn record_external_deposit(&mut self, token: ExternalToken, depositor: AccountId) {
+        self.external_deposits_by_account
+            .entry(depositor.clone())
+            .or_default()
+            .push(token.clone());
+        self.external_deposits.insert(token, depositor);
+    }
+
+    fn remove_external_deposit(&mut self, token: &ExternalToken) -> Option<AccountId> {
+        let depositor = self.external_deposits.remove(token)?;
+
+        if let Some(tokens) = self.external_deposits_by_account.get_mut(&depositor) {
+            tokens.retain(|t| t != token);
+            if tokens.is_empty() {
+                self.external_deposits_by_account.remove(&depositor);
+            }
+        }
+
+        Some(depositor)
+    }
+}
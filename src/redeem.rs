@@ -0,0 +1,204 @@
+use crate::events::{ContractEvent, NftRedeemLog, RedeemRecipeLog};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{
+    env,
+    json_types::{U64, U128},
+    near, require,
+    serde_json::{self, Value},
+};
+use near_sdk_contract_tools::{
+    nft::{Nep177Controller, TokenId, TokenMetadata},
+    owner::Owner,
+    standard::nep297::Event,
+};
+
+/// Criterion a burned token has to satisfy to fill one input of a recipe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum RedeemInput {
+    /// Exactly this token.
+    TokenId(TokenId),
+    /// Any token of a series. Tokens of series `s` have IDs of the form
+    /// `s:<edition>`.
+    Series(String),
+    /// Any token whose metadata `extra` is a JSON object with `key` set to
+    /// `value`.
+    Trait { key: String, value: String },
+}
+
+impl RedeemInput {
+    fn matches(&self, token_id: &TokenId, metadata: Option<&TokenMetadata>) -> bool {
+        match self {
+            Self::TokenId(expected) => token_id == expected,
+            Self::Series(series) => token_id
+                .strip_prefix(series.as_str())
+                .is_some_and(|edition| edition.starts_with(':')),
+            Self::Trait { key, value } => metadata
+                .and_then(|metadata| metadata.extra.as_deref())
+                .and_then(|extra| serde_json::from_str::<Value>(extra).ok())
+                .and_then(|extra| extra.get(key).cloned())
+                .is_some_and(|found| match found {
+                    Value::String(found) => &found == value,
+                    found => &found.to_string() == value,
+                }),
+        }
+    }
+}
+
+/// An owner-defined recipe: burning tokens that fill `inputs` mints one
+/// token with `output_metadata` to the caller.
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh, json])]
+pub struct RedeemRecipe {
+    pub inputs: Vec<RedeemInput>,
+    pub output_metadata: TokenMetadata,
+    /// Output tokens are minted as `<output_token_id_prefix><n>`, where `n`
+    /// counts redemptions of this recipe starting at 1.
+    pub output_token_id_prefix: String,
+    /// Maximum number of times the recipe can be redeemed.
+    pub max_redemptions: Option<u64>,
+    /// Unix epoch in milliseconds before which the recipe cannot be redeemed.
+    pub starts_at: Option<U64>,
+    /// Unix epoch in milliseconds after which the recipe cannot be redeemed.
+    pub ends_at: Option<U64>,
+    /// Number of times the recipe has been redeemed. Ignored when the recipe
+    /// is added.
+    #[serde(default)]
+    pub redeemed: u64,
+}
+
+#[near]
+impl MyNftContract {
+    pub fn add_redeem_recipe(&mut self, recipe_id: String, recipe: RedeemRecipe) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_add_redeem_recipe(recipe_id, recipe);
+    }
+
+    pub fn remove_redeem_recipe(&mut self, recipe_id: String) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_remove_redeem_recipe(recipe_id);
+    }
+
+    pub fn redeem_recipe(&self, recipe_id: String) -> Option<RedeemRecipe> {
+        self.redeem_recipes.get(&recipe_id).cloned()
+    }
+
+    pub fn redeem_recipes(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<(String, RedeemRecipe)> {
+        let it = self
+            .redeem_recipes
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .map(|(recipe_id, recipe)| (recipe_id.clone(), recipe.clone()));
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+
+    /// Burns `token_ids` and mints the recipe's output token to the caller.
+    /// `token_ids[i]` has to satisfy the recipe's `inputs[i]`. Storage freed
+    /// by the inputs pays for the output first, so the caller's storage
+    /// balance is only charged the net increase. Attached deposit covers the
+    /// caller's storage registration if needed.
+    #[payable]
+    pub fn nft_redeem(&mut self, recipe_id: String, token_ids: Vec<TokenId>) -> TokenId {
+        let predecessor = env::predecessor_account_id();
+
+        let recipe = self
+            .redeem_recipes
+            .get_mut(&recipe_id)
+            .unwrap_or_else(|| env::panic_str("Recipe does not exist"));
+
+        let now = env::block_timestamp_ms();
+        require!(
+            recipe.starts_at.is_none_or(|starts_at| now >= starts_at.0),
+            "Recipe is not active yet"
+        );
+        require!(
+            recipe.ends_at.is_none_or(|ends_at| now <= ends_at.0),
+            "Recipe has ended"
+        );
+        require!(
+            recipe
+                .max_redemptions
+                .is_none_or(|max_redemptions| recipe.redeemed < max_redemptions),
+            "Recipe supply is exhausted"
+        );
+        require!(
+            token_ids.len() == recipe.inputs.len(),
+            "Number of tokens does not match the recipe inputs"
+        );
+
+        recipe.redeemed += 1;
+        let recipe = recipe.clone();
+
+        let mut freed = 0;
+        for (input, token_id) in recipe.inputs.iter().zip(&token_ids) {
+            // Burning a wrapped token releases its original, which could then
+            // be wrapped and redeemed again.
+            require!(
+                !self.wrapped_tokens.contains_key(token_id),
+                format!("Wrapped token `{token_id}` cannot be redeemed; unwrap it first")
+            );
+            require!(
+                input.matches(token_id, self.token_metadata(token_id).as_ref()),
+                format!("Token `{token_id}` does not satisfy recipe input {input:?}")
+            );
+            freed += self.internal_burn(token_id, &predecessor);
+        }
+        self.unlock_freed_storage(&predecessor, freed);
+
+        let output_token_id = format!("{}{}", recipe.output_token_id_prefix, recipe.redeemed);
+        self.internal_mint(&output_token_id, &recipe.output_metadata, &predecessor, &[]);
+
+        ContractEvent::NftRedeem(vec![NftRedeemLog {
+            owner_id: predecessor,
+            recipe_id,
+            burned_token_ids: token_ids,
+            token_id: output_token_id.clone(),
+        }])
+        .emit();
+
+        output_token_id
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_add_redeem_recipe(&mut self, recipe_id: String, recipe: RedeemRecipe) {
+        require!(!recipe.inputs.is_empty(), "Recipe must have inputs");
+        require!(
+            !self.redeem_recipes.contains_key(&recipe_id),
+            "Recipe already exists"
+        );
+
+        self.redeem_recipes.insert(
+            recipe_id.clone(),
+            RedeemRecipe {
+                redeemed: 0,
+                ..recipe
+            },
+        );
+
+        ContractEvent::RedeemRecipeAdd(vec![RedeemRecipeLog { recipe_id }]).emit();
+    }
+
+    pub(crate) fn internal_remove_redeem_recipe(&mut self, recipe_id: String) {
+        require!(
+            self.redeem_recipes.remove(&recipe_id).is_some(),
+            "Recipe does not exist"
+        );
+
+        ContractEvent::RedeemRecipeRemove(vec![RedeemRecipeLog { recipe_id }]).emit();
+    }
+}
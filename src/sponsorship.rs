@@ -0,0 +1,303 @@
+//! Storage sponsorship. The owner funds a pool from which accounts without a
+//! storage account are registered with the minimum storage balance when they
+//! receive a token, so that they do not have to call `storage_deposit` first.
+//! Airdrops can fund recipients' storage from the pool, too.
+//! Sponsored storage cannot be withdrawn, and goes back to the pool when the
+//! account unregisters.
+
+use crate::events::{ContractEvent, SponsorPoolLog, SponsorshipCapsUpdateLog, StorageDepositLog};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, NearToken, Promise, env, json_types::U128, near, require};
+use near_sdk_contract_tools::{
+    hook::Hook,
+    nft::{Nep145Controller, Nep171Burn, Nep171Mint, Nep171Transfer},
+    owner::Owner,
+    standard::nep297::Event,
+};
+use std::fmt;
+
+/// Limits on how much of the pool is handed out. `None` leaves only the pool
+/// balance as the limit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct SponsorshipCaps {
+    /// Most storage a single account is sponsored, over all its
+    /// registrations. Storage returned to the pool when the account
+    /// unregisters still counts.
+    pub per_account: Option<NearToken>,
+    /// Most sponsored storage all accounts hold together.
+    pub total: Option<NearToken>,
+}
+
+/// Why storage could not be sponsored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SponsorshipError {
+    PoolExhausted,
+    AccountCap(AccountId),
+    TotalCap,
+}
+
+impl fmt::Display for SponsorshipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PoolExhausted => write!(f, "Sponsorship pool is exhausted"),
+            Self::AccountCap(account_id) => {
+                write!(f, "Account {account_id} has reached its sponsorship cap")
+            }
+            Self::TotalCap => write!(f, "Sponsorship total cap is reached"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [json])]
+pub struct SponsoredAccount {
+    pub account_id: AccountId,
+    pub amount: NearToken,
+}
+
+/// Registers unregistered token receivers from the pool before the storage
+/// accounting hook requires them to be registered.
+pub struct SponsorHook;
+
+impl Hook<MyNftContract, Nep171Mint<'_>> for SponsorHook {
+    fn hook<R>(
+        contract: &mut MyNftContract,
+        action: &Nep171Mint<'_>,
+        f: impl FnOnce(&mut MyNftContract) -> R,
+    ) -> R {
+        contract.sponsor_storage(&action.receiver_id.clone().into_owned());
+        f(contract)
+    }
+}
+
+impl Hook<MyNftContract, Nep171Transfer<'_>> for SponsorHook {
+    fn hook<R>(
+        contract: &mut MyNftContract,
+        action: &Nep171Transfer<'_>,
+        f: impl FnOnce(&mut MyNftContract) -> R,
+    ) -> R {
+        // Nested tokens are held by the contract account, whose storage is
+        // charged to the account nesting them.
+        if action.receiver_id.as_ref() != env::current_account_id() {
+            contract.sponsor_storage(&action.receiver_id.clone().into_owned());
+        }
+        f(contract)
+    }
+}
+
+impl Hook<MyNftContract, Nep171Burn<'_>> for SponsorHook {}
+
+#[near]
+impl MyNftContract {
+    /// Adds the attached deposit to the sponsorship pool.
+    #[payable]
+    pub fn fund_sponsor_pool(&mut self) -> NearToken {
+        Self::require_owner();
+
+        let amount = env::attached_deposit();
+        require!(!amount.is_zero(), "Attach the NEAR to add to the pool");
+
+        self.sponsor_pool = self.sponsor_pool.saturating_add(amount);
+
+        ContractEvent::SponsorPoolFund(vec![SponsorPoolLog {
+            amount,
+            balance: self.sponsor_pool,
+        }])
+        .emit();
+
+        self.sponsor_pool
+    }
+
+    /// Sends `amount` of the pool, or all of it, back to the owner. Storage
+    /// already sponsored stays with the sponsored accounts.
+    pub fn withdraw_sponsor_pool(&mut self, amount: Option<NearToken>) -> Promise {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_withdraw_sponsor_pool(amount)
+    }
+
+    pub fn set_sponsorship_caps(&mut self, caps: SponsorshipCaps) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_sponsorship_caps(caps);
+    }
+
+    pub fn sponsorship_caps(&self) -> SponsorshipCaps {
+        self.sponsorship_caps.clone()
+    }
+
+    pub fn sponsor_pool_balance(&self) -> NearToken {
+        self.sponsor_pool
+    }
+
+    /// Sponsored storage held by all accounts, which the total cap applies
+    /// to.
+    pub fn sponsored_total(&self) -> NearToken {
+        self.sponsored_total
+    }
+
+    /// Lists the accounts that hold sponsored storage, with how much each
+    /// holds.
+    pub fn sponsored_accounts(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<SponsoredAccount> {
+        let it = self
+            .sponsored_accounts
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .map(|(account_id, amount)| SponsoredAccount {
+                account_id: account_id.clone(),
+                amount: *amount,
+            });
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+}
+
+impl MyNftContract {
+    /// Sends `amount` of the pool, or all of it, to the caller.
+    pub(crate) fn internal_withdraw_sponsor_pool(&mut self, amount: Option<NearToken>) -> Promise {
+        let amount = amount.unwrap_or(self.sponsor_pool);
+        require!(!amount.is_zero(), "Nothing to withdraw");
+        self.sponsor_pool = self
+            .sponsor_pool
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("Amount exceeds the pool balance"));
+
+        ContractEvent::SponsorPoolWithdraw(vec![SponsorPoolLog {
+            amount,
+            balance: self.sponsor_pool,
+        }])
+        .emit();
+
+        Promise::new(env::predecessor_account_id()).transfer(amount)
+    }
+
+    pub(crate) fn internal_set_sponsorship_caps(&mut self, caps: SponsorshipCaps) {
+        self.sponsorship_caps = caps.clone();
+
+        ContractEvent::SponsorshipCapsUpdate(vec![SponsorshipCapsUpdateLog { caps }]).emit();
+    }
+
+    /// Registers `account_id` with the minimum storage balance from the pool,
+    /// unless it is already registered or the pool or a cap does not allow it.
+    /// An account left unregistered fails the storage accounting as usual.
+    fn sponsor_storage(&mut self, account_id: &AccountId) {
+        if Nep145Controller::get_storage_balance(self, account_id).is_ok() {
+            return;
+        }
+
+        let amount = Nep145Controller::get_storage_balance_bounds(self).min;
+        if self.check_sponsorship(account_id, amount).is_err() {
+            return;
+        }
+
+        Nep145Controller::deposit_to_storage_account(self, account_id, amount)
+            .unwrap_or_else(|e| env::panic_str(&format!("Storage deposit error: {e}")));
+
+        self.record_sponsored_storage(account_id, amount);
+
+        ContractEvent::StorageSponsor(vec![StorageDepositLog {
+            account_id: account_id.clone(),
+            amount,
+        }])
+        .emit();
+    }
+
+    /// Checks that the pool and the caps allow sponsoring `amount` of
+    /// `account_id`'s storage.
+    pub(crate) fn check_sponsorship(
+        &self,
+        account_id: &AccountId,
+        amount: NearToken,
+    ) -> Result<(), SponsorshipError> {
+        if amount > self.sponsor_pool {
+            return Err(SponsorshipError::PoolExhausted);
+        }
+
+        let received = self.sponsorship_received(account_id).saturating_add(amount);
+        if self
+            .sponsorship_caps
+            .per_account
+            .is_some_and(|cap| received > cap)
+        {
+            return Err(SponsorshipError::AccountCap(account_id.clone()));
+        }
+
+        let total = self.sponsored_total.saturating_add(amount);
+        if self.sponsorship_caps.total.is_some_and(|cap| total > cap) {
+            return Err(SponsorshipError::TotalCap);
+        }
+
+        Ok(())
+    }
+
+    /// Takes `amount` deposited to `account_id`'s storage balance from the
+    /// pool and records it as sponsored, so that it cannot be withdrawn.
+    pub(crate) fn record_sponsored_storage(&mut self, account_id: &AccountId, amount: NearToken) {
+        if let Err(e) = self.check_sponsorship(account_id, amount) {
+            env::panic_str(&e.to_string());
+        }
+
+        self.sponsor_pool = self.sponsor_pool.saturating_sub(amount);
+
+        let account_total = self.sponsored_storage(account_id).saturating_add(amount);
+        self.sponsored_accounts
+            .insert(account_id.clone(), account_total);
+        self.sponsorship_received.insert(
+            account_id.clone(),
+            self.sponsorship_received(account_id).saturating_add(amount),
+        );
+        self.sponsored_total = self.sponsored_total.saturating_add(amount);
+    }
+
+    /// Storage sponsored to `account_id` over all its registrations, which
+    /// the per-account cap applies to.
+    fn sponsorship_received(&self, account_id: &AccountId) -> NearToken {
+        self.sponsorship_received
+            .get(account_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The part of `account_id`'s storage balance that was sponsored.
+    pub(crate) fn sponsored_storage(&self, account_id: &AccountId) -> NearToken {
+        self.sponsored_accounts
+            .get(account_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the sponsored part of the `refund` of an account that
+    /// unregisters to the pool, and the rest of the refund.
+    pub(crate) fn reclaim_sponsored_storage(
+        &mut self,
+        account_id: &AccountId,
+        refund: NearToken,
+    ) -> NearToken {
+        let Some(sponsored) = self.sponsored_accounts.remove(account_id) else {
+            return refund;
+        };
+
+        let reclaimed = sponsored.min(refund);
+        self.sponsored_total = self.sponsored_total.saturating_sub(sponsored);
+        self.sponsor_pool = self.sponsor_pool.saturating_add(reclaimed);
+
+        ContractEvent::StorageSponsorReclaim(vec![StorageDepositLog {
+            account_id: account_id.clone(),
+            amount: reclaimed,
+        }])
+        .emit();
+
+        refund.saturating_sub(reclaimed)
+    }
+}
@@ -0,0 +1,230 @@
+use crate::events::{ContractEvent, StorageBoundsUpdateLog};
+use crate::traits::TokenTrait;
+use crate::{MyNftContract, MyNftContractExt, StorageKey};
+use near_sdk::{AccountId, NearToken, borsh, env, json_types::U64, near, require};
+use near_sdk_contract_tools::{
+    nft::{
+        Nep145Controller, Nep171ControllerInternal, Nep177ControllerInternal,
+        Nep181ControllerInternal, StorageBalanceBounds, TokenId, TokenMetadata,
+    },
+    owner::Owner,
+    standard::nep297::Event,
+};
+
+/// Storage balance bounds used when `new` is not given any.
+pub(crate) const DEFAULT_STORAGE_BALANCE_BOUNDS: StorageBalanceBounds = StorageBalanceBounds {
+    min: NearToken::from_millinear(7),
+    max: Some(NearToken::from_millinear(21)),
+};
+
+/// Bytes the protocol charges for every storage record on top of its key
+/// and value.
+const STORAGE_RECORD_OVERHEAD: u64 = 40;
+
+/// Storage a mint would use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [json])]
+pub struct StorageCostEstimate {
+    pub bytes: U64,
+    pub cost: NearToken,
+}
+
+#[near]
+impl MyNftContract {
+    /// Replaces the storage balance bounds. Registered accounts keep their
+    /// balances: accounts below a raised minimum are topped up on their next
+    /// mint, and accounts above a lowered maximum can only withdraw.
+    pub fn set_storage_bounds(&mut self, bounds: StorageBalanceBounds) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_storage_bounds(bounds);
+    }
+
+    /// Largest amount of storage a single token has used so far, in bytes,
+    /// not counting its traits and lockup. The minimum storage balance cannot
+    /// be set below its cost, nor below the cost of the smallest possible
+    /// token.
+    pub fn token_storage_usage(&self) -> U64 {
+        self.token_storage_usage.into()
+    }
+
+    /// Forgets the measured token storage, e.g. after an unusually large
+    /// token, so that the next mints measure it again.
+    pub fn reset_token_storage_usage(&mut self) {
+        Self::require_owner();
+
+        self.token_storage_usage = 0;
+    }
+
+    /// Computes the storage that minting `token_id` with `metadata` and
+    /// `traits` to `owner_id` would use, and thus lock from the owner's
+    /// storage balance. Registering an owner without a storage account comes
+    /// on top. The collection lockup recorded for the token is paid by the
+    /// contract and not included.
+    pub fn nft_storage_cost_estimate(
+        &self,
+        token_id: TokenId,
+        metadata: TokenMetadata,
+        owner_id: AccountId,
+        traits: Option<Vec<TokenTrait>>,
+    ) -> StorageCostEstimate {
+        let token_id_len = borsh_len(&token_id);
+
+        let mut bytes = record_size(
+            Self::slot_token_owner(&token_id).key.len(),
+            borsh_len(&owner_id),
+        ) + record_size(
+            Self::slot_token_metadata(&token_id).key.len(),
+            borsh_len(&metadata),
+        );
+
+        // Enumeration keeps all tokens and each owner's tokens in sets
+        // prefixed with the borsh-serialized `Tokens` and `OwnerTokens(owner)`
+        // keys of near-sdk-contract-tools' NEP-181 implementation.
+        bytes += unordered_set_insert_size(&Self::slot_tokens().key, 1, token_id_len);
+        bytes += unordered_set_insert_size(
+            &Self::slot_owner_tokens(&owner_id).key,
+            1 + borsh_len(&owner_id),
+            token_id_len,
+        );
+
+        let traits = traits.unwrap_or_default();
+        if !traits.is_empty() {
+            bytes += record_size(
+                borsh_len(&StorageKey::TokenTraits) + token_id_len,
+                borsh_len(&traits),
+            );
+            bytes += traits
+                .iter()
+                .map(|token_trait| self.trait_storage_estimate(token_trait, token_id_len))
+                .sum::<u64>();
+        }
+
+        StorageCostEstimate {
+            bytes: bytes.into(),
+            cost: env::storage_byte_cost().saturating_mul(u128::from(bytes)),
+        }
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_storage_bounds(&mut self, bounds: StorageBalanceBounds) {
+        if let Some(max) = bounds.max {
+            require!(
+                max >= bounds.min,
+                "Maximum storage balance cannot be below the minimum"
+            );
+        }
+
+        // Before the first mint, the smallest possible token is the lower
+        // limit.
+        let smallest_token_usage = self
+            .nft_storage_cost_estimate(
+                "0".to_string(),
+                TokenMetadata::default(),
+                env::current_account_id(),
+                None,
+            )
+            .bytes
+            .0;
+        let token_storage_cost = env::storage_byte_cost().saturating_mul(u128::from(
+            self.token_storage_usage.max(smallest_token_usage),
+        ));
+        require!(
+            bounds.min >= token_storage_cost,
+            format!(
+                "Minimum storage balance must cover the storage of one token ({} yoctoNEAR)",
+                token_storage_cost.as_yoctonear()
+            )
+        );
+
+        Nep145Controller::set_storage_balance_bounds(self, &bounds);
+
+        ContractEvent::StorageBoundsUpdate(vec![StorageBoundsUpdateLog { bounds }]).emit();
+    }
+
+    /// Storage used by indexing a new token with `token_trait`, apart from
+    /// the token's list of traits.
+    fn trait_storage_estimate(&self, token_trait: &TokenTrait, token_id_len: usize) -> u64 {
+        let index_key = (token_trait.key.clone(), token_trait.value.clone());
+
+        let token_set_prefix_len = borsh_len(&StorageKey::TraitTokenSet(index_key.clone()));
+        let mut bytes = iterable_set_insert_size(token_set_prefix_len, token_id_len);
+        if !self.trait_tokens.contains_key(&index_key) {
+            bytes += record_size(
+                borsh_len(&StorageKey::TraitTokens) + borsh_len(&index_key),
+                iterable_set_len(token_set_prefix_len),
+            );
+        }
+
+        // The first token with a value also counts and lists the value.
+        if !self.trait_counts.contains_key(&index_key) {
+            bytes += record_size(
+                borsh_len(&StorageKey::TraitCounts) + borsh_len(&index_key),
+                borsh_len(&0u32),
+            );
+
+            let value_set_prefix_len =
+                borsh_len(&StorageKey::TraitValueSet(token_trait.key.clone()));
+            bytes += iterable_set_insert_size(value_set_prefix_len, borsh_len(&token_trait.value));
+            if !self.trait_values.contains_key(&token_trait.key) {
+                bytes += record_size(
+                    borsh_len(&StorageKey::TraitValues) + borsh_len(&token_trait.key),
+                    iterable_set_len(value_set_prefix_len),
+                );
+            }
+        }
+
+        bytes
+    }
+
+    /// Records the storage used by a mint that started at
+    /// `storage_usage_before`.
+    pub(crate) fn record_token_storage_usage(&mut self, storage_usage_before: u64) {
+        let used = env::storage_usage().saturating_sub(storage_usage_before);
+        self.token_storage_usage = self.token_storage_usage.max(used);
+    }
+}
+
+fn borsh_len(value: &impl borsh::BorshSerialize) -> usize {
+    borsh::to_vec(value)
+        .unwrap_or_else(|_| env::panic_str("Cannot serialize value"))
+        .len()
+}
+
+fn record_size(key_len: usize, value_len: usize) -> u64 {
+    (key_len + value_len) as u64 + STORAGE_RECORD_OVERHEAD
+}
+
+/// Storage used by adding an element to a near-sdk `UnorderedSet` with a
+/// prefix of `prefix_len` bytes, stored in `slot_key`. The set itself is
+/// written to its slot the first time an element is added.
+fn unordered_set_insert_size(slot_key: &[u8], prefix_len: usize, element_len: usize) -> u64 {
+    // The element's index, keyed by prefix + b'i' + element, and the element,
+    // keyed by prefix + b'e' + index.
+    let mut bytes =
+        record_size(prefix_len + 1 + element_len, 8) + record_size(prefix_len + 1 + 8, element_len);
+
+    if !env::storage_has_key(slot_key) {
+        // Both prefixes and the element count.
+        bytes += record_size(slot_key.len(), 2 * (4 + prefix_len + 1) + 8);
+    }
+
+    bytes
+}
+
+/// Length of a serialized near-sdk `IterableSet` created with a prefix of
+/// `prefix_len` bytes: its element count and the prefixes of its element
+/// vector and index map.
+fn iterable_set_len(prefix_len: usize) -> usize {
+    4 + 2 * (4 + prefix_len + 1)
+}
+
+/// Storage used by adding an element to a near-sdk `IterableSet` with a
+/// prefix of `prefix_len` bytes, once the set itself is stored.
+fn iterable_set_insert_size(prefix_len: usize, element_len: usize) -> u64 {
+    // The element, keyed by prefix + b'v' + index, and its index, keyed by
+    // the SHA-256 hash of prefix + b'm' + element.
+    record_size(prefix_len + 1 + 4, element_len) + record_size(32, 4)
+}
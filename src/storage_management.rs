@@ -0,0 +1,311 @@
+//! NEP-145 storage management. Mirrors near-sdk-contract-tools' generated
+//! implementation, except that `storage_unregister` refuses while the
+//! account owns tokens, and with `force` burns them or hands them to a sink
+//! account according to the unregister policy.
+
+use crate::events::{ContractEvent, UnregisterPolicyUpdateLog};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, NearToken, Promise, assert_one_yocto, env, near, require};
+use near_sdk_contract_tools::{
+    hook::Hook,
+    nft::{
+        Nep145, Nep145Controller, Nep145ControllerInternal, Nep171Burn, Nep171Controller,
+        Nep171Mint, Nep171Transfer, Nep181Controller, StorageBalance, StorageBalanceBounds,
+        nep145::{
+            Nep145ForceUnregister, error::StorageUnregisterError,
+            hooks::Nep171StorageAccountingHook,
+        },
+        nep171::Nep171TransferAuthorization,
+    },
+    owner::Owner,
+    standard::nep297::Event,
+};
+
+/// Memo of the transfers made to the sink account.
+const UNREGISTER_MEMO: &str = "storage forced unregistration";
+
+/// What happens to the tokens of an account that unregisters with `force`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum UnregisterPolicy {
+    /// Burn the tokens.
+    #[default]
+    Burn,
+    /// Transfer the tokens to this account, which must be registered.
+    TransferTo(AccountId),
+}
+
+/// NEP-145 storage accounting for tokens. Like near-sdk-contract-tools'
+/// `Nep171StorageAccountingHook`, except that the storage of a token moved
+/// into the contract account's custody by nesting is charged to the account
+/// nesting it.
+pub struct StorageAccountingHook;
+
+impl Hook<MyNftContract, Nep171Mint<'_>> for StorageAccountingHook {
+    fn hook<R>(
+        contract: &mut MyNftContract,
+        action: &Nep171Mint<'_>,
+        f: impl FnOnce(&mut MyNftContract) -> R,
+    ) -> R {
+        Nep171StorageAccountingHook::hook(contract, action, f)
+    }
+}
+
+impl Hook<MyNftContract, Nep171Transfer<'_>> for StorageAccountingHook {
+    fn hook<R>(
+        contract: &mut MyNftContract,
+        action: &Nep171Transfer<'_>,
+        f: impl FnOnce(&mut MyNftContract) -> R,
+    ) -> R {
+        let account_id: AccountId = if action.receiver_id.as_ref() == env::current_account_id() {
+            action.sender_id.clone().into_owned()
+        } else {
+            action.receiver_id.clone().into_owned()
+        };
+
+        let storage_usage_before = env::storage_usage();
+        Nep145Controller::get_storage_balance(contract, &account_id)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+
+        let r = f(contract);
+
+        Nep145Controller::storage_accounting(contract, &account_id, storage_usage_before)
+            .unwrap_or_else(|e| env::panic_str(&format!("Storage accounting error: {e}")));
+
+        r
+    }
+}
+
+impl Hook<MyNftContract, Nep171Burn<'_>> for StorageAccountingHook {}
+
+/// Handles the tokens of a force-unregistered account before its storage
+/// account is removed.
+pub struct UnregisterHook;
+
+impl Hook<MyNftContract, Nep145ForceUnregister<'_>> for UnregisterHook {
+    fn hook<R>(
+        contract: &mut MyNftContract,
+        action: &Nep145ForceUnregister<'_>,
+        f: impl FnOnce(&mut MyNftContract) -> R,
+    ) -> R {
+        let account_id: AccountId = action.account_id.clone().into_owned();
+        let token_ids =
+            contract.with_tokens_for_owner(&account_id, |tokens| tokens.iter().collect::<Vec<_>>());
+        let policy = contract.unregister_policy.clone();
+
+        for token_id in &token_ids {
+            require!(
+                !contract.is_nested(token_id),
+                format!("Token `{token_id}` is nested; unnest it before unregistering")
+            );
+            require!(
+                policy != UnregisterPolicy::Burn || !contract.has_nested_tokens(token_id),
+                format!("Token `{token_id}` has nested tokens; unnest them before unregistering")
+            );
+        }
+
+        match policy {
+            UnregisterPolicy::Burn => {
+                for token_id in &token_ids {
+                    contract.internal_burn(token_id, &account_id);
+                }
+            }
+            UnregisterPolicy::TransferTo(sink_id) => {
+                contract.force_unregistering = true;
+                for token_id in token_ids {
+                    let transfer = Nep171Transfer::new(
+                        token_id,
+                        account_id.clone(),
+                        sink_id.clone(),
+                        Nep171TransferAuthorization::Owner,
+                    )
+                    .memo(UNREGISTER_MEMO);
+
+                    Nep171Controller::external_transfer(contract, &transfer)
+                        .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+                }
+                contract.force_unregistering = false;
+            }
+        }
+
+        f(contract)
+    }
+}
+
+impl Nep145ControllerInternal for MyNftContract {
+    type ForceUnregisterHook = UnregisterHook;
+}
+
+#[near]
+impl Nep145 for MyNftContract {
+    #[payable]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let bounds = Nep145Controller::get_storage_balance_bounds(self);
+
+        let attached = env::attached_deposit();
+        let amount = bounds.bound(attached, registration_only.unwrap_or(false));
+        let refund = attached.checked_sub(amount).unwrap_or_else(|| {
+            env::panic_str(&format!(
+                "Attached deposit {attached} is less than required {amount}"
+            ))
+        });
+        let predecessor = env::predecessor_account_id();
+
+        let storage_balance = Nep145Controller::deposit_to_storage_account(
+            self,
+            &account_id.unwrap_or_else(|| predecessor.clone()),
+            amount,
+        )
+        .unwrap_or_else(|e| env::panic_str(&format!("Storage deposit error: {e}")));
+
+        if !refund.is_zero() {
+            Promise::new(predecessor).transfer(refund);
+        }
+
+        storage_balance
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<NearToken>) -> StorageBalance {
+        assert_one_yocto();
+
+        let predecessor = env::predecessor_account_id();
+
+        let balance = Nep145Controller::get_storage_balance(self, &predecessor)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+
+        // Sponsored storage covers the locked balance first, and what it does
+        // not cover cannot be withdrawn either.
+        let withdrawable = balance.available.min(
+            balance
+                .total
+                .saturating_sub(self.sponsored_storage(&predecessor)),
+        );
+        let amount = amount.unwrap_or(withdrawable);
+        require!(
+            amount <= withdrawable,
+            "Sponsored storage balance cannot be withdrawn"
+        );
+
+        if amount.is_zero() {
+            return balance;
+        }
+
+        let new_balance =
+            Nep145Controller::withdraw_from_storage_account(self, &predecessor, amount)
+                .unwrap_or_else(|e| env::panic_str(&format!("Storage withdraw error: {e}")));
+
+        Promise::new(predecessor).transfer(amount);
+
+        new_balance
+    }
+
+    /// Without `force`, fails while the caller owns tokens. With `force`,
+    /// their tokens are burned or sent to the sink account first, and the
+    /// refund includes the storage that frees up. Sponsored storage goes back
+    /// to the sponsorship pool instead of being refunded.
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+
+        let predecessor = env::predecessor_account_id();
+
+        let Ok(balance) = Nep145Controller::get_storage_balance(self, &predecessor) else {
+            return false;
+        };
+
+        let refund = if force.unwrap_or(false) {
+            let storage_usage_before = env::storage_usage();
+
+            let available = Nep145Controller::force_unregister_storage_account(self, &predecessor)
+                .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+
+            let freed = env::storage_byte_cost().saturating_mul(u128::from(
+                storage_usage_before.saturating_sub(env::storage_usage()),
+            ));
+            available.saturating_add(freed).min(balance.total)
+        } else {
+            let token_count = self.with_tokens_for_owner(&predecessor, |tokens| tokens.len());
+            require!(
+                token_count == 0,
+                "Cannot unregister while owning tokens; use force to give them up"
+            );
+
+            match Nep145Controller::unregister_storage_account(self, &predecessor) {
+                Ok(refund) => refund,
+                Err(StorageUnregisterError::UnregisterWithLockedBalance(e)) => {
+                    env::panic_str(&format!(
+                        "Attempt to unregister from storage with locked balance: {e}"
+                    ));
+                }
+                Err(StorageUnregisterError::AccountNotRegistered(_)) => return false,
+            }
+        };
+
+        let refund = self.reclaim_sponsored_storage(&predecessor, refund);
+        if !refund.is_zero() {
+            Promise::new(predecessor).transfer(refund);
+        }
+        true
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        Nep145Controller::get_storage_balance(self, &account_id).ok()
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        Nep145Controller::get_storage_balance_bounds(self)
+    }
+}
+
+#[near]
+impl MyNftContract {
+    pub fn set_unregister_policy(&mut self, policy: UnregisterPolicy) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_unregister_policy(policy);
+    }
+
+    pub fn unregister_policy(&self) -> UnregisterPolicy {
+        self.unregister_policy.clone()
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_unregister_policy(&mut self, policy: UnregisterPolicy) {
+        if let UnregisterPolicy::TransferTo(sink_id) = &policy {
+            require!(
+                Nep145Controller::get_storage_balance(self, sink_id).is_ok(),
+                "Sink account must be registered for storage"
+            );
+        }
+
+        self.unregister_policy = policy.clone();
+
+        ContractEvent::UnregisterPolicyUpdate(vec![UnregisterPolicyUpdateLog { policy }]).emit();
+    }
+
+    /// Unlocks the storage balance `account_id` locked for `bytes` of
+    /// storage that have been freed since, up to what it has locked.
+    pub(crate) fn unlock_freed_storage(&mut self, account_id: &AccountId, bytes: u64) {
+        let Ok(balance) = Nep145Controller::get_storage_balance(self, account_id) else {
+            return;
+        };
+
+        let locked = balance.total.saturating_sub(balance.available);
+        let amount = env::storage_byte_cost()
+            .saturating_mul(bytes.into())
+            .min(locked);
+
+        if !amount.is_zero() {
+            Nep145Controller::unlock_storage(self, account_id, amount)
+                .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! On-chain media. Once the owner sets an SVG template, every token's `media`
+//! is an SVG rendered from the template and the token's traits, returned as a
+//! `data:` URI by `nft_token` and `nft_tokens`.
+
+use crate::events::{ContractEvent, SvgTemplateUpdateLog};
+use crate::traits::TokenTrait;
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{
+    base64::{Engine, engine::general_purpose::STANDARD},
+    env, near, require, serde_json,
+};
+use near_sdk_contract_tools::{
+    nft::{Nep171Controller, TokenId, nep171::LoadTokenMetadata},
+    owner::Owner,
+    standard::nep297::Event,
+};
+use std::error::Error;
+
+/// Maximum length of the SVG template, in bytes.
+const MAX_SVG_TEMPLATE_LEN: usize = 32 * 1024;
+
+const SVG_DATA_URI_PREFIX: &str = "data:image/svg+xml;base64,";
+
+/// Replaces the `media` and `media_hash` of a token's `nft_token` output with
+/// its rendered SVG, when there is a template.
+pub struct SvgMedia;
+
+impl LoadTokenMetadata<MyNftContract> for SvgMedia {
+    fn load(
+        contract: &MyNftContract,
+        token_id: &TokenId,
+        metadata: &mut std::collections::HashMap<String, serde_json::Value>,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(svg) = contract.render_svg(token_id) else {
+            return Ok(());
+        };
+
+        if let Some(token_metadata) = metadata
+            .get_mut("metadata")
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            token_metadata.insert(
+                "media".to_string(),
+                format!("{SVG_DATA_URI_PREFIX}{}", STANDARD.encode(&svg)).into(),
+            );
+            token_metadata.insert(
+                "media_hash".to_string(),
+                STANDARD.encode(env::sha256(svg.as_bytes())).into(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[near]
+impl MyNftContract {
+    /// Sets the SVG template tokens' media is rendered from, or goes back to
+    /// the stored `media` with `None`. `{{token_id}}` is replaced by the token
+    /// ID and `{{key}}` by the value of the token's trait `key`, or nothing if
+    /// the token does not have it. Values are XML-escaped.
+    pub fn set_svg_template(&mut self, template: Option<String>) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_svg_template(template);
+    }
+
+    pub fn svg_template(&self) -> Option<String> {
+        self.svg_template.clone()
+    }
+
+    /// The SVG `token_id`'s media is rendered as, if there is a template.
+    pub fn nft_svg(&self, token_id: TokenId) -> Option<String> {
+        self.render_svg(&token_id)
+    }
+}
+
+impl MyNftContract {
+    pub(crate) fn internal_set_svg_template(&mut self, template: Option<String>) {
+        if let Some(template) = &template {
+            require!(
+                template.len() <= MAX_SVG_TEMPLATE_LEN,
+                format!("SVG template cannot be longer than {MAX_SVG_TEMPLATE_LEN} bytes")
+            );
+            require!(
+                template.trim_start().starts_with("<svg"),
+                "SVG template must start with an <svg> element"
+            );
+        }
+
+        self.svg_template = template.clone();
+
+        ContractEvent::SvgTemplateUpdate(vec![SvgTemplateUpdateLog { template }]).emit();
+    }
+
+    fn render_svg(&self, token_id: &TokenId) -> Option<String> {
+        let template = self.svg_template.as_ref()?;
+        self.token_owner(token_id)?;
+
+        let traits = self
+            .token_traits
+            .get(token_id)
+            .map_or(&[][..], Vec::as_slice);
+
+        Some(render(template, token_id, traits))
+    }
+}
+
+/// Fills in the placeholders of `template`.
+fn render(template: &str, token_id: &TokenId, traits: &[TokenTrait]) -> String {
+    let mut svg = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+
+        svg.push_str(&rest[..start]);
+
+        let name = rest[start + 2..start + 2 + len].trim();
+        let value = if name == "token_id" {
+            Some(token_id.as_str())
+        } else {
+            traits
+                .iter()
+                .find(|token_trait| token_trait.key == name)
+                .map(|token_trait| token_trait.value.as_str())
+        };
+        push_escaped(&mut svg, value.unwrap_or_default());
+
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    svg.push_str(rest);
+    svg
+}
+
+fn push_escaped(svg: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => svg.push_str("&amp;"),
+            '<' => svg.push_str("&lt;"),
+            '>' => svg.push_str("&gt;"),
+            '"' => svg.push_str("&quot;"),
+            '\'' => svg.push_str("&apos;"),
+            c => svg.push(c),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use crate::access_list::AccessMode;
+use crate::events::{AdminActionLog, AdminActionQueueLog, ContractEvent, TimelockDelayUpdateLog};
+use crate::fees::FeePolicy;
+use crate::lockup::LockupPolicy;
+use crate::metadata_policy::MetadataPolicy;
+use crate::multisig::MultisigConfig;
+use crate::rbac::Role;
+use crate::receiver::ReceiverPolicy;
+use crate::redeem::RedeemRecipe;
+use crate::sponsorship::SponsorshipCaps;
+use crate::storage_management::UnregisterPolicy;
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{
+    AccountId, CryptoHash, NearToken, Promise, assert_one_yocto, env,
+    json_types::{Base58CryptoHash, Base64VecU8, U64, U128},
+    near, require,
+};
+use near_sdk_contract_tools::{
+    nft::{ContractMetadata, Nep177Controller, StorageBalanceBounds},
+    owner::{Owner, OwnerExternal, OwnerInternal},
+    standard::nep297::Event,
+};
+
+/// An admin action that has to wait out the timelock delay once one is set.
+/// Methods that a role allows, like blocking accounts or overriding a token
+/// lockup, are not timelocked; granting the roles is.
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    SetContractMetadata(ContractMetadata),
+    SetFeePolicy(Option<FeePolicy>),
+    SetAccessMode(AccessMode),
+    SetCollectionLockup(LockupPolicy),
+    SetReceiverPolicy(ReceiverPolicy),
+    SetStorageBounds(StorageBalanceBounds),
+    SetUnregisterPolicy(UnregisterPolicy),
+    SetSponsorshipCaps(SponsorshipCaps),
+    WithdrawSponsorPool(Option<NearToken>),
+    SetMetadataPolicy(MetadataPolicy),
+    SetSvgTemplate(Option<String>),
+    SetResolveUrisInViews(bool),
+    SetHistoryRetention(u32),
+    AddRedeemRecipe(String, Box<RedeemRecipe>),
+    RemoveRedeemRecipe(String),
+    SetTimelockDelay(u32),
+    GrantRole(AccountId, Role),
+    RevokeRole(AccountId, Role),
+    SetMultisigConfig(Option<MultisigConfig>),
+    RenounceOwner,
+    /// Deploys new contract code with this SHA-256 hash. The code itself is
+    /// passed to `execute_admin_action`.
+    Upgrade(Base58CryptoHash),
+}
+
+#[derive(Clone, Debug)]
+#[near(serializers = [borsh, json])]
+pub struct QueuedAdminAction {
+    pub action: AdminAction,
+    /// Earliest execution time, in milliseconds since the Unix epoch.
+    pub executable_at: U64,
+}
+
+impl OwnerInternal for MyNftContract {}
+
+/// The standard ownership methods, except that once a timelock delay is set,
+/// a proposed owner can only accept after the delay has passed since the
+/// proposal, and renouncing ownership has to be queued as an admin action.
+#[near]
+impl OwnerExternal for MyNftContract {
+    fn own_get_owner(&self) -> Option<AccountId> {
+        Self::slot_owner().read()
+    }
+
+    fn own_get_proposed_owner(&self) -> Option<AccountId> {
+        Self::slot_proposed_owner().read()
+    }
+
+    #[payable]
+    fn own_renounce_owner(&mut self) {
+        assert_one_yocto();
+        self.require_not_timelocked();
+
+        Owner::renounce_owner(self);
+    }
+
+    #[payable]
+    fn own_propose_owner(&mut self, account_id: Option<AccountId>) {
+        assert_one_yocto();
+
+        self.owner_proposed_at = account_id.is_some().then(env::block_timestamp_ms);
+        Owner::propose_owner(self, account_id);
+    }
+
+    #[payable]
+    fn own_accept_owner(&mut self) {
+        assert_one_yocto();
+
+        if let Some(proposed_at) = self.owner_proposed_at.take() {
+            let acceptable_at = proposed_at.saturating_add(self.timelock_delay_ms());
+            require!(
+                env::block_timestamp_ms() >= acceptable_at,
+                format!("Ownership cannot be accepted before {acceptable_at}")
+            );
+        }
+
+        Owner::accept_owner(self);
+    }
+}
+
+#[near]
+impl MyNftContract {
+    /// Sets the timelock delay directly. Only possible while there is no
+    /// delay; afterwards, queue a `set_timelock_delay` action.
+    pub fn set_timelock_delay(&mut self, delay_seconds: u32) {
+        Self::require_owner();
+        self.require_not_timelocked();
+
+        self.internal_set_timelock_delay(delay_seconds);
+    }
+
+    pub fn timelock_delay(&self) -> u32 {
+        self.timelock_delay_seconds
+    }
+
+    /// Queues `action` for execution after the timelock delay and returns
+    /// its ID.
+    pub fn queue_admin_action(&mut self, action: AdminAction) -> u64 {
+        Self::require_owner();
+
+        let action_id = self.next_admin_action_id;
+        self.next_admin_action_id += 1;
+
+        let executable_at = env::block_timestamp_ms().saturating_add(self.timelock_delay_ms());
+        self.admin_actions.insert(
+            action_id,
+            QueuedAdminAction {
+                action: action.clone(),
+                executable_at: executable_at.into(),
+            },
+        );
+
+        ContractEvent::AdminActionQueue(vec![AdminActionQueueLog {
+            action_id,
+            action,
+            executable_at: executable_at.into(),
+        }])
+        .emit();
+
+        action_id
+    }
+
+    pub fn cancel_admin_action(&mut self, action_id: u64) {
+        Self::require_owner();
+
+        require!(
+            self.admin_actions.remove(&action_id).is_some(),
+            "Admin action does not exist"
+        );
+
+        ContractEvent::AdminActionCancel(vec![AdminActionLog { action_id }]).emit();
+    }
+
+    /// Executes a queued action once its delay has passed. `code` is the
+    /// contract code for an `upgrade` action, and is ignored otherwise.
+    pub fn execute_admin_action(&mut self, action_id: u64, code: Option<Base64VecU8>) {
+        Self::require_owner();
+
+        let queued = self
+            .admin_actions
+            .get(&action_id)
+            .cloned()
+            .unwrap_or_else(|| env::panic_str("Admin action does not exist"));
+        require!(
+            env::block_timestamp_ms() >= queued.executable_at.0,
+            "Admin action is still timelocked"
+        );
+        self.admin_actions.remove(&action_id);
+
+        ContractEvent::AdminActionExecute(vec![AdminActionLog { action_id }]).emit();
+
+        match queued.action {
+            AdminAction::SetContractMetadata(metadata) => self.set_contract_metadata(&metadata),
+            AdminAction::SetFeePolicy(policy) => self.internal_set_fee_policy(policy),
+            AdminAction::SetAccessMode(mode) => self.internal_set_access_mode(mode),
+            AdminAction::SetCollectionLockup(policy) => self.internal_set_collection_lockup(policy),
+            AdminAction::SetReceiverPolicy(policy) => self.internal_set_receiver_policy(policy),
+            AdminAction::SetStorageBounds(bounds) => self.internal_set_storage_bounds(bounds),
+            AdminAction::SetUnregisterPolicy(policy) => self.internal_set_unregister_policy(policy),
+            AdminAction::SetSponsorshipCaps(caps) => self.internal_set_sponsorship_caps(caps),
+            AdminAction::WithdrawSponsorPool(amount) => {
+                self.internal_withdraw_sponsor_pool(amount);
+            }
+            AdminAction::SetMetadataPolicy(policy) => self.internal_set_metadata_policy(policy),
+            AdminAction::SetSvgTemplate(template) => self.internal_set_svg_template(template),
+            AdminAction::SetResolveUrisInViews(enabled) => {
+                self.internal_set_resolve_uris_in_views(enabled)
+            }
+            AdminAction::SetHistoryRetention(retention) => {
+                self.internal_set_history_retention(retention)
+            }
+            AdminAction::AddRedeemRecipe(recipe_id, recipe) => {
+                self.internal_add_redeem_recipe(recipe_id, *recipe)
+            }
+            AdminAction::RemoveRedeemRecipe(recipe_id) => {
+                self.internal_remove_redeem_recipe(recipe_id)
+            }
+            AdminAction::SetTimelockDelay(delay_seconds) => {
+                self.internal_set_timelock_delay(delay_seconds)
+            }
+            AdminAction::GrantRole(account_id, role) => self.internal_grant_role(account_id, role),
+            AdminAction::RevokeRole(account_id, role) => {
+                self.internal_revoke_role(account_id, role)
+            }
+            AdminAction::SetMultisigConfig(config) => self.internal_set_multisig_config(config),
+            AdminAction::RenounceOwner => Owner::renounce_owner(self),
+            AdminAction::Upgrade(code_hash) => {
+                let code = code
+                    .unwrap_or_else(|| env::panic_str("Pass the code to deploy"))
+                    .0;
+                require!(
+                    env::sha256_array(&code) == CryptoHash::from(code_hash),
+                    "Code does not match the queued hash"
+                );
+
+                Promise::new(env::current_account_id()).deploy_contract(code);
+            }
+        }
+    }
+
+    pub fn admin_action(&self, action_id: u64) -> Option<QueuedAdminAction> {
+        self.admin_actions.get(&action_id).cloned()
+    }
+
+    pub fn admin_actions(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<(u64, QueuedAdminAction)> {
+        let it = self
+            .admin_actions
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .map(|(action_id, queued)| (*action_id, queued.clone()));
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+}
+
+impl MyNftContract {
+    /// Rejects direct calls to timelocked setters once a delay is set.
+    pub(crate) fn require_not_timelocked(&self) {
+        require!(
+            self.timelock_delay_seconds == 0,
+            "This action is timelocked; use queue_admin_action"
+        );
+    }
+
+    fn timelock_delay_ms(&self) -> u64 {
+        u64::from(self.timelock_delay_seconds) * 1000
+    }
+
+    fn internal_set_timelock_delay(&mut self, delay_seconds: u32) {
+        self.timelock_delay_seconds = delay_seconds;
+
+        ContractEvent::TimelockDelayUpdate(vec![TimelockDelayUpdateLog { delay_seconds }]).emit();
+    }
+}
@@ -0,0 +1,219 @@
+//! Token traits: key/value attributes set at mint, returned by `nft_token`
+//! under `traits` and indexed by key and value.
+
+use crate::{MyNftContract, MyNftContractExt, StorageKey};
+use near_sdk::{AccountId, env, json_types::U128, near, require, serde_json, store::IterableSet};
+use near_sdk_contract_tools::nft::{
+    Nep145Controller, Nep171Controller, Token, TokenId, nep171::LoadTokenMetadata,
+};
+use std::{collections::BTreeMap, error::Error};
+
+/// Maximum number of traits a token can have.
+const MAX_TRAITS_PER_TOKEN: usize = 32;
+
+/// Maximum length of a trait's key and of its value, in bytes.
+const MAX_TRAIT_LEN: usize = 256;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh, json])]
+pub struct TokenTrait {
+    pub key: String,
+    pub value: String,
+}
+
+/// Adds a token's traits to its `nft_token` output.
+pub struct TokenTraits;
+
+impl LoadTokenMetadata<MyNftContract> for TokenTraits {
+    fn load(
+        contract: &MyNftContract,
+        token_id: &TokenId,
+        metadata: &mut std::collections::HashMap<String, serde_json::Value>,
+    ) -> Result<(), Box<dyn Error>> {
+        let traits = contract
+            .token_traits
+            .get(token_id)
+            .cloned()
+            .unwrap_or_default();
+        metadata.insert("traits".to_string(), serde_json::to_value(traits)?);
+        Ok(())
+    }
+}
+
+#[near]
+impl MyNftContract {
+    /// Lists the tokens with the trait `key` set to `value`.
+    pub fn nft_tokens_with_trait(
+        &self,
+        key: String,
+        value: String,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<Token> {
+        let Some(token_ids) = self.trait_tokens.get(&(key, value)) else {
+            return vec![];
+        };
+
+        let it = token_ids
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .map(|token_id| {
+                self.load_token(token_id).unwrap_or_else(|| {
+                    env::panic_str(&format!(
+                        "Inconsistent state: Token `{token_id}` has traits but could not be loaded."
+                    ))
+                })
+            });
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+
+    /// Counts the tokens with the trait `key` set to `value`.
+    pub fn nft_trait_count(&self, key: String, value: String) -> u32 {
+        self.trait_counts
+            .get(&(key, value))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Counts the tokens with each value of the trait `key`, paging through
+    /// the values in the order they were first used.
+    pub fn nft_trait_counts(
+        &self,
+        key: String,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> BTreeMap<String, u32> {
+        let Some(values) = self.trait_values.get(&key) else {
+            return BTreeMap::new();
+        };
+
+        let it = values
+            .iter()
+            .skip(from_index.map_or(0, |i| i.0 as usize))
+            .map(|value| {
+                let count = self
+                    .trait_counts
+                    .get(&(key.clone(), value.clone()))
+                    .copied()
+                    .unwrap_or_default();
+                (value.clone(), count)
+            });
+
+        if let Some(limit) = limit {
+            it.take(limit as usize).collect()
+        } else {
+            it.collect()
+        }
+    }
+}
+
+impl MyNftContract {
+    /// Stores and indexes the traits of the newly minted `token_id`, charging
+    /// their storage to `owner_id`.
+    pub(crate) fn record_token_traits(
+        &mut self,
+        token_id: &TokenId,
+        owner_id: &AccountId,
+        traits: &[TokenTrait],
+    ) {
+        if traits.is_empty() {
+            return;
+        }
+
+        require_valid_traits(traits);
+
+        let storage_usage_before = env::storage_usage();
+
+        for token_trait in traits {
+            let index_key = (token_trait.key.clone(), token_trait.value.clone());
+            let token_ids = self
+                .trait_tokens
+                .entry(index_key.clone())
+                .or_insert_with(|| IterableSet::new(StorageKey::TraitTokenSet(index_key.clone())));
+            token_ids.insert(token_id.clone());
+            token_ids.flush();
+
+            let count = self.trait_counts.entry(index_key).or_default();
+            *count += 1;
+            if *count == 1 {
+                let values = self
+                    .trait_values
+                    .entry(token_trait.key.clone())
+                    .or_insert_with(|| {
+                        IterableSet::new(StorageKey::TraitValueSet(token_trait.key.clone()))
+                    });
+                values.insert(token_trait.value.clone());
+                values.flush();
+            }
+        }
+
+        self.token_traits.insert(token_id.clone(), traits.to_vec());
+
+        // The maps only write to storage when flushed.
+        self.token_traits.flush();
+        self.trait_tokens.flush();
+        self.trait_counts.flush();
+        self.trait_values.flush();
+
+        Nep145Controller::storage_accounting(self, owner_id, storage_usage_before)
+            .unwrap_or_else(|e| env::panic_str(&format!("Storage accounting error: {e}")));
+    }
+
+    /// Removes the traits of a burned token from the index.
+    pub(crate) fn clear_token_traits(&mut self, token_id: &TokenId) {
+        let Some(traits) = self.token_traits.remove(token_id) else {
+            return;
+        };
+
+        for token_trait in traits {
+            let index_key = (token_trait.key, token_trait.value);
+            if let Some(token_ids) = self.trait_tokens.get_mut(&index_key) {
+                token_ids.remove(token_id);
+                if token_ids.is_empty() {
+                    self.trait_tokens.remove(&index_key);
+                }
+            }
+
+            let Some(count) = self.trait_counts.get_mut(&index_key) else {
+                continue;
+            };
+            *count -= 1;
+            if *count > 0 {
+                continue;
+            }
+            self.trait_counts.remove(&index_key);
+
+            let (key, value) = index_key;
+            if let Some(values) = self.trait_values.get_mut(&key) {
+                values.remove(&value);
+                if values.is_empty() {
+                    self.trait_values.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+fn require_valid_traits(traits: &[TokenTrait]) {
+    require!(
+        traits.len() <= MAX_TRAITS_PER_TOKEN,
+        format!("A token cannot have more than {MAX_TRAITS_PER_TOKEN} traits")
+    );
+
+    for (i, token_trait) in traits.iter().enumerate() {
+        require!(!token_trait.key.is_empty(), "Trait keys cannot be empty");
+        require!(
+            token_trait.key.len() <= MAX_TRAIT_LEN && token_trait.value.len() <= MAX_TRAIT_LEN,
+            format!("Trait keys and values cannot be longer than {MAX_TRAIT_LEN} bytes")
+        );
+        require!(
+            traits[..i].iter().all(|other| other.key != token_trait.key),
+            format!("Trait `{}` is set more than once", token_trait.key)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::MyNftContract;
-use near_sdk::{env, log};
-use near_sdk_contract_tools::{hook::Hook, nft::*};
+use crate::events::{ContractEvent, TransferStorageLog};
+use near_sdk::{env, require};
+use near_sdk_contract_tools::{hook::Hook, nft::*, pause::Pause, standard::nep297::Event};
 
 pub struct TransferHook;
 
@@ -11,22 +12,48 @@ impl Hook<MyNftContract, Nep171Transfer<'_>> for TransferHook {
         f: impl FnOnce(&mut MyNftContract) -> R,
     ) -> R {
         // Log, check preconditions, save state, etc.
-        log!(
-            "NEP-171 transfer from {} to {} of {} tokens",
-            transfer.sender_id,
-            transfer.receiver_id,
-            transfer.token_id
+        require!(
+            !contract.is_nested(&transfer.token_id),
+            "Nested tokens must be unnested before they can be transferred"
         );
 
+        let previous_owner_id = contract.token_owner(&transfer.token_id);
+
+        // Returning a token to its previous owner or handing it to the
+        // unregister sink is never blocked, and does not restart its lockup
+        // cooldown.
+        let checked = !transfer.revert && !contract.force_unregistering;
+        if checked {
+            MyNftContract::require_unpaused();
+            contract.require_unlocked(&transfer.token_id);
+            contract.require_account_access(&transfer.sender_id.clone().into_owned());
+            contract.require_account_access(&transfer.receiver_id.clone().into_owned());
+            if let Some(previous_owner_id) = &previous_owner_id {
+                contract.require_account_access(previous_owner_id);
+                contract.collect_transfer_fee(transfer, previous_owner_id);
+            }
+        }
+
         let storage_usage_before = env::storage_usage();
 
         let r = f(contract); // execute wrapped function
 
         let storage_usage_after = env::storage_usage();
-        log!(
-            "Storage delta: {}",
-            storage_usage_after - storage_usage_before
-        );
+        ContractEvent::TransferStorage(vec![TransferStorageLog {
+            token_id: transfer.token_id.clone(),
+            receiver_id: transfer.receiver_id.clone().into_owned(),
+            storage_delta: storage_usage_after as i64 - storage_usage_before as i64,
+        }])
+        .emit();
+
+        // Nesting and unnesting keep the token in the same tree of tokens.
+        if checked && !contract.moving_custody {
+            contract.record_lockup_transfer(&transfer.token_id);
+        }
+
+        if let Some(previous_owner_id) = previous_owner_id {
+            contract.record_transfer(transfer, previous_owner_id);
+        }
 
         r
     }
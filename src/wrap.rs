@@ -0,0 +1,177 @@
+use crate::events::{ContractEvent, NftWrapLog};
+use crate::receiver::{ExternalToken, ReceiverAction};
+use crate::{MyNftContract, MyNftContractExt};
+use near_sdk::{AccountId, Gas, NearToken, PromiseOrValue, env, log, near, serde_json};
+use near_sdk_contract_tools::{
+    nft::{Nep145, Token, TokenId, TokenMetadata, ext_nep171},
+    pause::Pause,
+    standard::nep297::Event,
+};
+
+/// Gas attached to the `nft_token` call that fetches the original metadata.
+const GAS_FOR_NFT_TOKEN: Gas = Gas::from_tgas(5);
+
+/// Gas attached to the callback that mints the wrapped or replacement token.
+const GAS_FOR_RESOLVE_WRAP: Gas = Gas::from_tgas(20);
+
+/// Gas attached to `nft_transfer` when releasing an original token.
+const GAS_FOR_RELEASE_TRANSFER: Gas = Gas::from_tgas(15);
+
+/// Gas attached to the callback that resolves a release.
+const GAS_FOR_RESOLVE_RELEASE: Gas = Gas::from_tgas(5);
+
+#[near]
+impl MyNftContract {
+    /// Returns the original token a wrapped token stands for.
+    pub fn wrapped_token_source(&self, token_id: TokenId) -> Option<ExternalToken> {
+        self.wrapped_tokens.get(&token_id).cloned()
+    }
+
+    /// Returns the burned original a token was minted to replace.
+    pub fn burned_token_source(&self, token_id: TokenId) -> Option<ExternalToken> {
+        self.burned_external_tokens.get(&token_id).cloned()
+    }
+
+    /// Mints the wrapped or replacement token once the original metadata is
+    /// known. Returns `true` to send the original back when minting is not
+    /// possible.
+    #[private]
+    pub fn resolve_wrap(
+        &mut self,
+        #[callback_result] original: Result<Option<Token>, near_sdk::PromiseError>,
+        source: ExternalToken,
+        owner_id: AccountId,
+        action: ReceiverAction,
+    ) -> bool {
+        let Ok(Some(original)) = original else {
+            log!(
+                "Could not load token {} from {}",
+                source.token_id,
+                source.contract_id
+            );
+            return true;
+        };
+
+        if original.owner_id != env::current_account_id() {
+            log!("Token {} is not held by this contract", source.token_id);
+            return true;
+        }
+
+        if Self::is_paused() {
+            log!("Tokens cannot be wrapped while the contract is paused");
+            return true;
+        }
+
+        if let Err(e) = self.check_account_access(&owner_id) {
+            log!("{}", e);
+            return true;
+        }
+
+        let storage_balance = self.storage_balance_of(owner_id.clone());
+        if storage_balance.is_none_or(|b| b.total < self.storage_balance_bounds().min) {
+            log!("{} must register storage before wrapping tokens", owner_id);
+            return true;
+        }
+
+        let metadata = original
+            .extensions_metadata
+            .get("metadata")
+            .and_then(|metadata| serde_json::from_value::<TokenMetadata>(metadata.clone()).ok())
+            .unwrap_or_default();
+
+        let wrapped_token_id = wrapped_token_id(&source);
+        self.internal_mint(&wrapped_token_id, &metadata, &owner_id, &[]);
+
+        let log = NftWrapLog {
+            owner_id,
+            token_id: wrapped_token_id.clone(),
+            source: source.clone(),
+        };
+        if action == ReceiverAction::BurnToMint {
+            self.burned_external_tokens.insert(wrapped_token_id, source);
+            ContractEvent::NftBurnToMint(vec![log]).emit();
+        } else {
+            self.wrapped_tokens.insert(wrapped_token_id, source);
+            ContractEvent::NftWrap(vec![log]).emit();
+        }
+
+        false
+    }
+
+    /// Keeps a released original as an external deposit of `owner_id` if it
+    /// could not be sent back, so it can still be withdrawn later.
+    #[private]
+    pub fn resolve_release(&mut self, source: ExternalToken, owner_id: AccountId) -> bool {
+        if near_sdk::is_promise_success() {
+            true
+        } else {
+            self.record_external_deposit(source, owner_id);
+            false
+        }
+    }
+}
+
+impl MyNftContract {
+    /// Starts wrapping, or burning and replacing, a token received through
+    /// `nft_on_transfer`.
+    pub(crate) fn wrap_external_token(
+        &mut self,
+        source: ExternalToken,
+        owner_id: AccountId,
+        action: ReceiverAction,
+    ) -> PromiseOrValue<bool> {
+        if source.contract_id == env::current_account_id() {
+            log!("Tokens of this collection cannot be wrapped or replaced");
+            return PromiseOrValue::Value(true);
+        }
+
+        ext_nep171::ext(source.contract_id.clone())
+            .with_static_gas(GAS_FOR_NFT_TOKEN)
+            .nft_token(source.token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_WRAP)
+                    .resolve_wrap(source, owner_id, action),
+            )
+            .into()
+    }
+
+    /// Forgets the source of a burned replacement token.
+    pub(crate) fn clear_burned_token_source(&mut self, token_id: &TokenId) {
+        self.burned_external_tokens.remove(token_id);
+    }
+
+    /// Sends the original of a burned wrapped token to `owner_id`.
+    pub(crate) fn release_wrapped_token(&mut self, token_id: &TokenId, owner_id: &AccountId) {
+        let Some(source) = self.wrapped_tokens.remove(token_id) else {
+            return;
+        };
+
+        ContractEvent::NftUnwrap(vec![NftWrapLog {
+            owner_id: owner_id.clone(),
+            token_id: token_id.clone(),
+            source: source.clone(),
+        }])
+        .emit();
+
+        ext_nep171::ext(source.contract_id.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(GAS_FOR_RELEASE_TRANSFER)
+            .nft_transfer(
+                owner_id.clone(),
+                source.token_id.clone(),
+                None,
+                Some("unwrap".to_string()),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_RELEASE)
+                    .resolve_release(source, owner_id.clone()),
+            );
+    }
+}
+
+/// Wrapped and replacement tokens are minted as `<source contract>:<source token ID>`.
+fn wrapped_token_id(source: &ExternalToken) -> TokenId {
+    format!("{}:{}", source.contract_id, source.token_id)
+}
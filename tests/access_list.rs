@@ -0,0 +1,168 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_workspaces::{Account, Contract, Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::events::AccountListLog;
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const TOKEN_ID: &str = "0";
+
+async fn account_status(nft_contract: &Contract, account: &Account) -> anyhow::Result<Value> {
+    Ok(nft_contract
+        .call("account_status")
+        .args_json(json!({"account_id": account.id()}))
+        .view()
+        .await?
+        .json()?)
+}
+
+#[tokio::test]
+async fn test_blocklist_rejects_transfers() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+
+    let res = nft_contract
+        .call("block_accounts")
+        .args_json(json!({"account_ids": [bob.id()]}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let blocked: Vec<AccountListLog> = common::contract_events(&res, "accounts_block");
+    assert_eq!(blocked.len(), 1);
+    assert_eq!(blocked[0].account_ids[0].as_str(), bob.id().as_str());
+
+    // The list has no effect while the contract is open.
+    let status = account_status(&nft_contract, &bob).await?;
+    assert_eq!(status["blocked"], true);
+    assert_eq!(status["permitted"], true);
+
+    common::owner_call(
+        &nft_contract,
+        "set_access_mode",
+        json!({"mode": "blocklist"}),
+    )
+    .await?;
+    let status = account_status(&nft_contract, &bob).await?;
+    assert_eq!(status["permitted"], false);
+
+    let res = alice
+        .call(nft_contract.id(), "nft_transfer")
+        .args_json((
+            bob.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains(&format!("Account {} is blocked", bob.id())));
+
+    // Approving a blocked account fails too.
+    let res = alice
+        .call(nft_contract.id(), "nft_approve")
+        .args_json((TOKEN_ID, bob.id(), Option::<String>::None))
+        .max_gas()
+        .deposit(NearToken::from_yoctonear(550000000000000000000))
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    common::owner_call(
+        &nft_contract,
+        "unblock_accounts",
+        json!({"account_ids": [bob.id()]}),
+    )
+    .await?;
+
+    let res = alice
+        .call(nft_contract.id(), "nft_transfer")
+        .args_json((
+            bob.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_allowlist_rejects_mints_to_other_accounts() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::owner_call(
+        &nft_contract,
+        "allow_accounts",
+        json!({"account_ids": [alice.id()]}),
+    )
+    .await?;
+    common::owner_call(
+        &nft_contract,
+        "set_access_mode",
+        json!({"mode": "allowlist"}),
+    )
+    .await?;
+
+    let mint = |owner: &Account| {
+        nft_contract
+            .call("nft_mint")
+            .args_json(json!({
+                "token_id": format!("id-{}", owner.id()),
+                "metadata": {"title": "Allowed"},
+                "owner_id": owner.id(),
+            }))
+            .max_gas()
+            .deposit(NearToken::from_millinear(21))
+            .transact()
+    };
+
+    assert!(mint(&alice).await?.is_success());
+
+    let err = mint(&bob).await?.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains(&format!("Account {} is not on the allowlist", bob.id())));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_owner_can_change_lists() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "allow_accounts")
+        .args_json(json!({"account_ids": [alice.id()]}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let status = account_status(&nft_contract, &alice).await?;
+    assert_eq!(status["allowed"], false);
+
+    Ok(())
+}
@@ -0,0 +1,537 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_sdk_contract_tools::nft::StorageBalance;
+use near_workspaces::{Account, Worker, network::Sandbox, types::NearToken};
+
+fn airdrop_args(recipients: &[&Account], funding: &str) -> Value {
+    let recipients = recipients
+        .iter()
+        .enumerate()
+        .map(|(i, recipient)| json!({"account_id": recipient.id(), "token_id_suffix": i.to_string()}))
+        .collect::<Vec<_>>();
+
+    json!({
+        "token_id_prefix": "drop-",
+        "metadata": {"title": "Airdrop"},
+        "recipients": recipients,
+        "funding": funding,
+    })
+}
+
+#[tokio::test]
+async fn test_airdrop_funds_storage_from_attached_deposit() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = nft_contract
+        .call("nft_airdrop")
+        .args_json(airdrop_args(&[&alice, &bob], "attached_deposit"))
+        .max_gas()
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let summary = res.json::<Value>()?;
+    assert_eq!(summary["minted"].as_array().unwrap().len(), 2);
+    assert!(summary["skipped"].as_array().unwrap().is_empty());
+    assert_eq!(summary["storage_funded"], "14000000000000000000000");
+
+    assert_eq!(
+        common::token_owner(&nft_contract, "drop-0").await?,
+        Some(alice.id().to_string())
+    );
+    assert_eq!(
+        common::token_owner(&nft_contract, "drop-1").await?,
+        Some(bob.id().to_string())
+    );
+
+    let balance: Option<StorageBalance> = nft_contract
+        .call("storage_balance_of")
+        .args_json((alice.id(),))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(
+        balance.unwrap().total.as_yoctonear(),
+        7000000000000000000000
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_airdrop_skips_recipients_instead_of_failing() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let charlie = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    // `drop-0` is already taken.
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "drop-0".into(),
+        Some(nft_contract.id()),
+    )
+    .await?;
+
+    // Enough to register only one of the remaining recipients.
+    let res = nft_contract
+        .call("nft_airdrop")
+        .args_json(airdrop_args(&[&alice, &bob, &charlie], "attached_deposit"))
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let summary = res.json::<Value>()?;
+    assert_eq!(
+        summary["minted"],
+        json!([{"account_id": bob.id(), "token_id": "drop-1"}])
+    );
+    let skipped = summary["skipped"].as_array().unwrap();
+    assert_eq!(skipped.len(), 2);
+    assert_eq!(skipped[0]["account_id"], json!(alice.id()));
+    assert_eq!(skipped[1]["account_id"], json!(charlie.id()));
+
+    assert_eq!(
+        common::token_owner(&nft_contract, "drop-0").await?,
+        Some(nft_contract.id().to_string())
+    );
+    assert_eq!(common::token_owner(&nft_contract, "drop-2").await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_airdrop_funds_storage_from_sponsor_pool() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    // The contract's own balance is not spent while the pool is empty.
+    let res = nft_contract
+        .call("nft_airdrop")
+        .args_json(airdrop_args(&[&alice], "sponsor_pool"))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let summary = res.json::<Value>()?;
+    assert_eq!(summary["skipped"].as_array().unwrap().len(), 1);
+    assert_eq!(common::token_owner(&nft_contract, "drop-0").await?, None);
+
+    let res = nft_contract
+        .call("fund_sponsor_pool")
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = nft_contract
+        .call("nft_airdrop")
+        .args_json(airdrop_args(&[&alice], "sponsor_pool"))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let summary = res.json::<Value>()?;
+    assert_eq!(summary["minted"].as_array().unwrap().len(), 1);
+    let funded: NearToken = near_sdk::serde_json::from_value(summary["storage_funded"].clone())?;
+
+    let pool: NearToken = nft_contract
+        .call("sponsor_pool_balance")
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(
+        pool.as_yoctonear(),
+        NearToken::from_millinear(10).as_yoctonear() - funded.as_yoctonear()
+    );
+
+    assert_eq!(
+        common::token_owner(&nft_contract, "drop-0").await?,
+        Some(alice.id().to_string())
+    );
+
+    // Storage paid from the pool is sponsored and cannot be withdrawn.
+    let balance: StorageBalance = nft_contract
+        .call("storage_balance_of")
+        .args_json((alice.id(),))
+        .view()
+        .await?
+        .json::<Option<StorageBalance>>()?
+        .unwrap();
+    let res = alice
+        .call(nft_contract.id(), "storage_withdraw")
+        .args_json(json!({"amount": balance.total}))
+        .max_gas()
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Sponsored storage balance cannot be withdrawn"));
+
+    let res = alice
+        .call(nft_contract.id(), "storage_withdraw")
+        .args_json(json!({}))
+        .max_gas()
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(res.json::<StorageBalance>()?.total, balance.total);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_owner_can_airdrop() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "nft_airdrop")
+        .args_json(airdrop_args(&[&alice], "attached_deposit"))
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    assert_eq!(common::token_owner(&nft_contract, "drop-0").await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_airdrop_job_can_be_processed_by_anyone() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let charlie = worker.dev_create_account().await?;
+    let cranker = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    // `drop-2` is already taken.
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "drop-2".into(),
+        Some(nft_contract.id()),
+    )
+    .await?;
+
+    let res = nft_contract
+        .call("create_airdrop_job")
+        .args_json(json!({
+            "token_id_prefix": "drop-",
+            "metadata": {"title": "Airdrop"},
+            "funding": "attached_deposit",
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let job_id = res.json::<u64>()?;
+
+    for chunk in [
+        json!([
+            {"account_id": alice.id(), "token_id_suffix": "0"},
+            {"account_id": bob.id(), "token_id_suffix": "1"},
+        ]),
+        json!([{"account_id": charlie.id(), "token_id_suffix": "2"}]),
+    ] {
+        let res = nft_contract
+            .call("add_airdrop_recipients")
+            .args_json(json!({"job_id": job_id, "recipients": chunk}))
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    let res = nft_contract
+        .call("seal_airdrop_job")
+        .args_json(json!({"job_id": job_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = cranker
+        .call(nft_contract.id(), "process_airdrop")
+        .args_json(json!({"job_id": job_id, "limit": 2}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let job: Value = nft_contract
+        .call("airdrop_job")
+        .args_json(json!({"job_id": job_id}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(job["cursor"], 2);
+    assert_eq!(job["minted"], 2);
+
+    let res = cranker
+        .call(nft_contract.id(), "process_airdrop")
+        .args_json(json!({"job_id": job_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let job: Value = nft_contract
+        .call("airdrop_job")
+        .args_json(json!({"job_id": job_id}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(job["cursor"], 3);
+    assert_eq!(job["skipped"], 1);
+    assert_eq!(job["storage_budget"], "0");
+
+    let skips: Vec<Value> = nft_contract
+        .call("airdrop_job_skips")
+        .args_json(json!({"job_id": job_id}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(skips.len(), 1);
+    assert_eq!(skips[0]["account_id"], json!(charlie.id()));
+
+    assert_eq!(
+        common::token_owner(&nft_contract, "drop-1").await?,
+        Some(bob.id().to_string())
+    );
+
+    // The job is done.
+    let res = cranker
+        .call(nft_contract.id(), "process_airdrop")
+        .args_json(json!({"job_id": job_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_airdrop_job_skips_recipients_whose_mint_would_fail() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = nft_contract
+        .call("create_airdrop_job")
+        .args_json(json!({
+            "token_id_prefix": "drop-",
+            "metadata": {"title": "Airdrop"},
+            "funding": "attached_deposit",
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let job_id = res.json::<u64>()?;
+
+    let res = nft_contract
+        .call("add_airdrop_recipients")
+        .args_json(json!({
+            "job_id": job_id,
+            "recipients": [
+                {"account_id": alice.id(), "token_id_suffix": "0"},
+                {"account_id": bob.id(), "token_id_suffix": "1"},
+            ],
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = nft_contract
+        .call("seal_airdrop_job")
+        .args_json(json!({"job_id": job_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // The job's metadata no longer passes the policy.
+    let res = nft_contract
+        .call("set_metadata_policy")
+        .args_json(json!({
+            "policy": {
+                "required_fields": ["description"],
+                "max_lengths": {},
+                "url_schemes": [],
+                "check_hashes": false,
+                "extra_json": false,
+            },
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = nft_contract
+        .call("process_airdrop")
+        .args_json(json!({"job_id": job_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let job: Value = nft_contract
+        .call("airdrop_job")
+        .args_json(json!({"job_id": job_id}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(job["cursor"], 2);
+    assert_eq!(job["minted"], 0);
+    assert_eq!(job["skipped"], 2);
+
+    let skips: Vec<Value> = nft_contract
+        .call("airdrop_job_skips")
+        .args_json(json!({"job_id": job_id}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(skips.len(), 2);
+    assert_eq!(skips[0]["reason"], "Metadata `description` is required");
+
+    assert_eq!(common::token_owner(&nft_contract, "drop-0").await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_airdrop_job_refunds_its_funder() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let minter = worker.dev_create_account().await?;
+    let alice = worker.dev_create_account().await?;
+    let cranker = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = nft_contract
+        .call("grant_role")
+        .args_json(json!({"account_id": minter.id(), "role": "minter"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = minter
+        .call(nft_contract.id(), "create_airdrop_job")
+        .args_json(json!({
+            "token_id_prefix": "drop-",
+            "metadata": {"title": "Airdrop"},
+            "funding": "attached_deposit",
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let job_id = res.json::<u64>()?;
+
+    // Only the funder adds to the budget.
+    let res = nft_contract
+        .call("add_airdrop_recipients")
+        .args_json(json!({"job_id": job_id, "recipients": []}))
+        .max_gas()
+        .deposit(NearToken::from_millinear(10))
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Only the job's funder can add to its storage budget"));
+
+    for (method, args) in [
+        (
+            "add_airdrop_recipients",
+            json!({
+                "job_id": job_id,
+                "recipients": [{"account_id": alice.id(), "token_id_suffix": "0"}],
+            }),
+        ),
+        ("seal_airdrop_job", json!({"job_id": job_id})),
+    ] {
+        let res = minter
+            .call(nft_contract.id(), method)
+            .args_json(args)
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    let funder_balance = minter.view_account().await?.balance;
+
+    let res = cranker
+        .call(nft_contract.id(), "process_airdrop")
+        .args_json(json!({"job_id": job_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let summary = res.json::<Value>()?;
+    let funded: NearToken = near_sdk::serde_json::from_value(summary["storage_funded"].clone())?;
+
+    let refund = NearToken::from_millinear(100).as_yoctonear() - funded.as_yoctonear();
+    assert_eq!(
+        minter.view_account().await?.balance.as_yoctonear(),
+        funder_balance.as_yoctonear() + refund
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_owner_can_add_airdrop_recipients() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = nft_contract
+        .call("create_airdrop_job")
+        .args_json(json!({
+            "token_id_prefix": "drop-",
+            "metadata": {"title": "Airdrop"},
+            "funding": "sponsor_pool",
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let job_id = res.json::<u64>()?;
+
+    let res = alice
+        .call(nft_contract.id(), "add_airdrop_recipients")
+        .args_json(json!({
+            "job_id": job_id,
+            "recipients": [{"account_id": alice.id(), "token_id_suffix": "0"}],
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    Ok(())
+}
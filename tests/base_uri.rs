@@ -0,0 +1,220 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_sdk_contract_tools::nft::{ContractMetadata, Token};
+use near_workspaces::{Contract, Worker, network::Sandbox, types::NearToken};
+
+const TOKEN_ID: &str = "id-0";
+
+async fn token_media(nft_contract: &Contract, method: &str) -> anyhow::Result<(Value, Value)> {
+    let token: Token = nft_contract
+        .call(method)
+        .args_json(json!({"token_id": TOKEN_ID}))
+        .view()
+        .await?
+        .json()?;
+    let metadata = &token.extensions_metadata["metadata"];
+
+    Ok((metadata["media"].clone(), metadata["reference"].clone()))
+}
+
+#[tokio::test]
+async fn test_relative_paths_are_resolved_against_base_uri() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let nft_contract = common::deploy_nft_contract(&worker).await?;
+
+    common::owner_call(
+        &nft_contract,
+        "new",
+        json!({
+            "owner_id": nft_contract.id(),
+            "metadata": ContractMetadata {
+                base_uri: Some("https://example.com/assets/".to_string()),
+                ..common::contract_metadata()
+            },
+        }),
+    )
+    .await?;
+
+    let res = nft_contract
+        .call("nft_mint")
+        .args_json(json!({
+            "token_id": TOKEN_ID,
+            "metadata": {"media": "images/0.png", "reference": "ipfs://bafy/0.json"},
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(21))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // The standard views return the stored values unless told otherwise.
+    assert_eq!(
+        token_media(&nft_contract, "nft_token").await?,
+        (json!("images/0.png"), json!("ipfs://bafy/0.json"))
+    );
+    assert_eq!(
+        token_media(&nft_contract, "nft_token_resolved").await?,
+        (
+            json!("https://example.com/assets/images/0.png"),
+            json!("ipfs://bafy/0.json")
+        )
+    );
+
+    common::owner_call(
+        &nft_contract,
+        "nft_set_token_base_uri",
+        json!({"token_id": TOKEN_ID, "base_uri": "ar://collection"}),
+    )
+    .await?;
+    common::owner_call(
+        &nft_contract,
+        "set_resolve_uris_in_views",
+        json!({"enabled": true}),
+    )
+    .await?;
+
+    assert_eq!(
+        token_media(&nft_contract, "nft_token").await?,
+        (
+            json!("ar://collection/images/0.png"),
+            json!("ipfs://bafy/0.json")
+        )
+    );
+
+    let res = nft_contract
+        .call("nft_set_token_base_uri")
+        .args_json(json!({"token_id": TOKEN_ID, "base_uri": "collection/"}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Base URI must be absolute"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_metadata_policy_requires_relative_paths() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::owner_call(
+        &nft_contract,
+        "set_metadata_policy",
+        json!({
+            "policy": {
+                "required_fields": [],
+                "max_lengths": {},
+                "url_schemes": [],
+                "check_hashes": false,
+                "extra_json": false,
+                "media_paths": "relative",
+            },
+        }),
+    )
+    .await?;
+
+    let res = nft_contract
+        .call("nft_mint")
+        .args_json(json!({
+            "token_id": TOKEN_ID,
+            "metadata": {"media": "https://example.com/0.png"},
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(21))
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(
+        format!("{err:?}").contains("Metadata `media` must be a path relative to the base URI")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relative_paths_skip_url_schemes() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::owner_call(
+        &nft_contract,
+        "set_metadata_policy",
+        json!({
+            "policy": {
+                "required_fields": [],
+                "max_lengths": {},
+                "url_schemes": ["https"],
+                "check_hashes": false,
+                "extra_json": false,
+                "media_paths": "relative",
+            },
+        }),
+    )
+    .await?;
+
+    let res = nft_contract
+        .call("nft_mint")
+        .args_json(json!({
+            "token_id": TOKEN_ID,
+            "metadata": {"media": "images/0.png", "reference": "example.com:8080/0.json"},
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(21))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_absolute_uris_need_a_scheme_and_authority() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::owner_call(
+        &nft_contract,
+        "set_metadata_policy",
+        json!({
+            "policy": {
+                "required_fields": [],
+                "max_lengths": {},
+                "url_schemes": [],
+                "check_hashes": false,
+                "extra_json": false,
+                "media_paths": "absolute",
+            },
+        }),
+    )
+    .await?;
+
+    let mint = |token_id: &'static str, media: &'static str| {
+        nft_contract
+            .call("nft_mint")
+            .args_json(json!({"token_id": token_id, "metadata": {"media": media}}))
+            .max_gas()
+            .deposit(NearToken::from_millinear(21))
+            .transact()
+    };
+
+    let err = mint("id-0", "example.com:8080/a.png")
+        .await?
+        .into_result()
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("Metadata `media` must be an absolute URI"));
+
+    assert!(
+        mint("id-1", "https://example.com/a.png")
+            .await?
+            .is_success()
+    );
+    assert!(
+        mint("id-2", "data:image/png;base64,iVBORw0KGgo=")
+            .await?
+            .is_success()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,332 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_workspaces::{
+    Account, Contract, Worker,
+    network::Sandbox,
+    result::ExecutionFinalResult,
+    types::{Gas, NearToken},
+};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+/// Per-entry gas bound and the gas of a batch apart from its entries, as in
+/// `src/batch_transfer.rs`.
+const GAS_PER_BATCH_TRANSFER: Gas = Gas::from_tgas(8);
+const GAS_FOR_BATCH_TRANSFER: Gas = Gas::from_tgas(30);
+/// `(300 - 30 - 10 - 5) / (8 + 4)`: what is left of 300 Tgas after the batch
+/// itself, the receiver call and the resolve callback, over the gas of a
+/// transfer plus reverting it.
+const MAX_BATCH_TRANSFER_SIZE: u64 = 21;
+
+async fn mint_tokens(
+    nft_contract: &Contract,
+    owner: &Account,
+    token_ids: &[&str],
+) -> anyhow::Result<()> {
+    for token_id in token_ids {
+        common::mint_nft(
+            nft_contract.as_account(),
+            nft_contract.id(),
+            token_id.to_string(),
+            Some(owner.id()),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn batch_transfer(
+    sender: &Account,
+    nft_contract: &Contract,
+    transfers: Vec<(&Account, &str)>,
+) -> anyhow::Result<ExecutionFinalResult> {
+    let transfers = transfers
+        .into_iter()
+        .map(|(receiver, token_id)| {
+            json!({
+                "receiver_id": receiver.id(),
+                "token_id": token_id,
+                "approval_id": null,
+                "memo": null,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(sender
+        .call(nft_contract.id(), "nft_batch_transfer")
+        .args_json(json!({ "transfers": transfers }))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?)
+}
+
+fn nft_transfer_events(res: &ExecutionFinalResult) -> Vec<Value> {
+    res.logs()
+        .into_iter()
+        .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+        .filter_map(|event| near_sdk::serde_json::from_str::<Value>(event).ok())
+        .filter(|event| event["event"] == "nft_transfer")
+        .collect()
+}
+
+#[tokio::test]
+async fn test_batch_transfer_emits_one_event() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let charlie = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    mint_tokens(&nft_contract, &alice, &["id-0", "id-1", "id-2"]).await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+    common::register_user(&nft_contract, charlie.id()).await?;
+
+    let res = batch_transfer(
+        &alice,
+        &nft_contract,
+        vec![(&bob, "id-0"), (&charlie, "id-1"), (&bob, "id-2")],
+    )
+    .await?;
+    assert!(res.is_success());
+
+    assert_eq!(
+        common::token_owner(&nft_contract, "id-0").await?,
+        Some(bob.id().to_string())
+    );
+    assert_eq!(
+        common::token_owner(&nft_contract, "id-1").await?,
+        Some(charlie.id().to_string())
+    );
+    assert_eq!(
+        common::token_owner(&nft_contract, "id-2").await?,
+        Some(bob.id().to_string())
+    );
+
+    let events = nft_transfer_events(&res);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["data"].as_array().unwrap().len(), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_transfer_is_all_or_nothing() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    mint_tokens(&nft_contract, &alice, &["id-0"]).await?;
+    mint_tokens(&nft_contract, &bob, &["id-1"]).await?;
+
+    // Alice does not own `id-1`, so the transfer of `id-0` is rolled back too.
+    let res = batch_transfer(&alice, &nft_contract, vec![(&bob, "id-0"), (&bob, "id-1")]).await?;
+    assert!(res.is_failure());
+
+    assert_eq!(
+        common::token_owner(&nft_contract, "id-0").await?,
+        Some(alice.id().to_string())
+    );
+    assert_eq!(
+        common::token_owner(&nft_contract, "id-1").await?,
+        Some(bob.id().to_string())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_batch_size_fits_in_gas_limit() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let token_ids = (0..=MAX_BATCH_TRANSFER_SIZE)
+        .map(|i| format!("id-{i}"))
+        .collect::<Vec<_>>();
+    let token_ids = token_ids.iter().map(String::as_str).collect::<Vec<_>>();
+
+    mint_tokens(&nft_contract, &alice, &token_ids).await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+
+    let (single, full) = token_ids.split_at(1);
+
+    let res = batch_transfer(&alice, &nft_contract, vec![(&bob, single[0])]).await?;
+    assert!(res.is_success());
+    let single_gas = res.total_gas_burnt.as_gas();
+
+    let res = batch_transfer(
+        &alice,
+        &nft_contract,
+        full.iter().map(|token_id| (&bob, *token_id)).collect(),
+    )
+    .await?;
+    assert!(res.is_success());
+    let full_gas = res.total_gas_burnt.as_gas();
+
+    // Every entry past the first costs the same, so the difference between
+    // the two batches is the gas of `MAX_BATCH_TRANSFER_SIZE - 1` entries.
+    let per_transfer_gas = (full_gas - single_gas) / (MAX_BATCH_TRANSFER_SIZE - 1);
+    assert!(per_transfer_gas <= GAS_PER_BATCH_TRANSFER.as_gas());
+    assert!(single_gas.saturating_sub(per_transfer_gas) <= GAS_FOR_BATCH_TRANSFER.as_gas());
+
+    for token_id in token_ids {
+        assert_eq!(
+            common::token_owner(&nft_contract, token_id).await?,
+            Some(bob.id().to_string())
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_transfer_rejects_oversized_batch() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let token_ids = (0..=MAX_BATCH_TRANSFER_SIZE)
+        .map(|i| format!("id-{i}"))
+        .collect::<Vec<_>>();
+
+    let res = batch_transfer(
+        &alice,
+        &nft_contract,
+        token_ids
+            .iter()
+            .map(|token_id| (&bob, token_id.as_str()))
+            .collect(),
+    )
+    .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Batch cannot have more than"));
+
+    Ok(())
+}
+
+async fn batch_transfer_call(
+    sender: &Account,
+    nft_contract: &Contract,
+    receiver: &Contract,
+    token_ids: &[&str],
+    msg: &str,
+) -> anyhow::Result<ExecutionFinalResult> {
+    Ok(sender
+        .call(nft_contract.id(), "nft_batch_transfer_call")
+        .args_json(json!({
+            "receiver_id": receiver.id(),
+            "token_ids": token_ids,
+            "msg": msg,
+        }))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?)
+}
+
+#[tokio::test]
+async fn test_batch_transfer_call_keeps_all_tokens() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, token_receiver_contract, _) = common::init_contracts(&worker).await?;
+
+    mint_tokens(&nft_contract, &alice, &["id-0", "id-1"]).await?;
+    common::register_user(&nft_contract, token_receiver_contract.id()).await?;
+
+    let res = batch_transfer_call(
+        &alice,
+        &nft_contract,
+        &token_receiver_contract,
+        &["id-0", "id-1"],
+        "keep-it-later",
+    )
+    .await?;
+    assert!(res.is_success());
+    assert_eq!(res.json::<Vec<bool>>()?, vec![true, true]);
+
+    for token_id in ["id-0", "id-1"] {
+        assert_eq!(
+            common::token_owner(&nft_contract, token_id).await?,
+            Some(token_receiver_contract.id().to_string())
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_transfer_call_partial_return() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, token_receiver_contract, _) = common::init_contracts(&worker).await?;
+
+    mint_tokens(&nft_contract, &alice, &["id-0", "id-1", "id-2", "id-3"]).await?;
+    common::register_user(&nft_contract, token_receiver_contract.id()).await?;
+
+    for (msg, token_ids) in [
+        ("return-first-now", ["id-0", "id-1"]),
+        ("return-first-later", ["id-2", "id-3"]),
+    ] {
+        let res = batch_transfer_call(
+            &alice,
+            &nft_contract,
+            &token_receiver_contract,
+            &token_ids,
+            msg,
+        )
+        .await?;
+        assert!(res.is_success());
+
+        // Only the returned token is transferred back.
+        let events = nft_transfer_events(&res);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1]["data"][0]["token_ids"], json!([token_ids[0]]));
+        assert_eq!(res.json::<Vec<bool>>()?, vec![false, true]);
+
+        assert_eq!(
+            common::token_owner(&nft_contract, token_ids[0]).await?,
+            Some(alice.id().to_string())
+        );
+        assert_eq!(
+            common::token_owner(&nft_contract, token_ids[1]).await?,
+            Some(token_receiver_contract.id().to_string())
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_transfer_call_receiver_panic_returns_all_tokens() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, token_receiver_contract, _) = common::init_contracts(&worker).await?;
+
+    mint_tokens(&nft_contract, &alice, &["id-0", "id-1"]).await?;
+    common::register_user(&nft_contract, token_receiver_contract.id()).await?;
+
+    let res = batch_transfer_call(
+        &alice,
+        &nft_contract,
+        &token_receiver_contract,
+        &["id-0", "id-1"],
+        "incorrect message",
+    )
+    .await?;
+    assert!(res.is_success());
+    assert_eq!(res.json::<Vec<bool>>()?, vec![false, false]);
+
+    for token_id in ["id-0", "id-1"] {
+        assert_eq!(
+            common::token_owner(&nft_contract, token_id).await?,
+            Some(alice.id().to_string())
+        );
+    }
+
+    Ok(())
+}
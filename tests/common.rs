@@ -5,8 +5,12 @@ use near_contract_standards::non_fungible_token::TokenId;
 
 use near_sdk::AccountId;
 use near_sdk::json_types::U64;
-use near_sdk::serde_json::json;
-use near_sdk_contract_tools::nft::{ContractMetadata, TokenMetadata};
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::serde_json::{self, Value, json};
+use near_sdk_contract_tools::nft::{
+    ContractMetadata, StorageBalance, StorageBalanceBounds, Token, TokenMetadata,
+};
+use near_workspaces::result::ExecutionFinalResult;
 use near_workspaces::types::NearToken;
 use near_workspaces::{Account, Contract, DevNetwork, Worker};
 
@@ -55,9 +59,41 @@ static APPROVAL_RECEIVER_CONTRACT_WASM: LazyLock<Vec<u8>> = LazyLock::new(|| {
 pub async fn init_contracts(
     worker: &Worker<impl DevNetwork>,
 ) -> anyhow::Result<(Contract, Contract, Contract)> {
-    let nft_contract = worker.dev_deploy(&NFT_CONTRACT_WASM).await?;
+    let nft_contract = init_nft_contract(worker).await?;
 
-    let metadata = ContractMetadata {
+    let token_receiver_contract = worker.dev_deploy(&TOKEN_RECEIVER_CONTRACT_WASM).await?;
+    let approval_receiver_contract = worker.dev_deploy(&APPROVAL_RECEIVER_CONTRACT_WASM).await?;
+
+    Ok((
+        nft_contract,
+        token_receiver_contract,
+        approval_receiver_contract,
+    ))
+}
+
+/// Deploys and initializes a standalone copy of the NFT contract, e.g. to act
+/// as an external collection.
+pub async fn init_nft_contract(worker: &Worker<impl DevNetwork>) -> anyhow::Result<Contract> {
+    let nft_contract = deploy_nft_contract(worker).await?;
+
+    let res = nft_contract
+        .call("new")
+        .args_json(json!({"owner_id": nft_contract.id(), "metadata": contract_metadata()}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(nft_contract)
+}
+
+/// Deploys the NFT contract without initializing it.
+pub async fn deploy_nft_contract(worker: &Worker<impl DevNetwork>) -> anyhow::Result<Contract> {
+    Ok(worker.dev_deploy(&NFT_CONTRACT_WASM).await?)
+}
+
+pub fn contract_metadata() -> ContractMetadata {
+    ContractMetadata {
         spec: "nft-2.1.0".to_string(),
         name: "MyNftContract".to_string(),
         symbol: "MNFT".to_string(),
@@ -65,31 +101,36 @@ pub async fn init_contracts(
         base_uri: None,
         reference: None,
         reference_hash: None,
-    };
-    let res = nft_contract
-        .call("new")
-        .args_json(json!({"owner_id": nft_contract.id(), "metadata": metadata}))
+    }
+}
+
+/// Registers `account_id` with the minimum storage balance.
+pub async fn register_user(contract: &Contract, account_id: &AccountId) -> anyhow::Result<()> {
+    let bounds: StorageBalanceBounds = contract
+        .call("storage_balance_bounds")
+        .view()
+        .await?
+        .json()?;
+
+    let res = contract
+        .call("storage_deposit")
+        .args_json((account_id, Option::<bool>::None))
         .max_gas()
+        .deposit(NearToken::from_yoctonear(bounds.min.as_yoctonear()))
         .transact()
         .await?;
     assert!(res.is_success());
 
-    let token_receiver_contract = worker.dev_deploy(&TOKEN_RECEIVER_CONTRACT_WASM).await?;
-    let approval_receiver_contract = worker.dev_deploy(&APPROVAL_RECEIVER_CONTRACT_WASM).await?;
-
-    Ok((
-        nft_contract,
-        token_receiver_contract,
-        approval_receiver_contract,
-    ))
+    Ok(())
 }
 
-pub async fn register_user(contract: &Contract, account_id: &AccountId) -> anyhow::Result<()> {
+/// Calls `method` as the contract account, which owns the contract, and
+/// expects it to succeed.
+pub async fn owner_call(contract: &Contract, method: &str, args: Value) -> anyhow::Result<()> {
     let res = contract
-        .call("storage_deposit")
-        .args_json((account_id, Option::<bool>::None))
+        .call(method)
+        .args_json(args)
         .max_gas()
-        .deposit(NearToken::from_yoctonear(7000000000000000000000))
         .transact()
         .await?;
     assert!(res.is_success());
@@ -97,6 +138,29 @@ pub async fn register_user(contract: &Contract, account_id: &AccountId) -> anyho
     Ok(())
 }
 
+pub async fn storage_balance_of(
+    contract: &Contract,
+    account: &Account,
+) -> anyhow::Result<Option<StorageBalance>> {
+    Ok(contract
+        .call("storage_balance_of")
+        .args_json((account.id(),))
+        .view()
+        .await?
+        .json()?)
+}
+
+pub async fn token_owner(contract: &Contract, token_id: &str) -> anyhow::Result<Option<String>> {
+    let token: Option<Token> = contract
+        .call("nft_token")
+        .args_json((token_id,))
+        .view()
+        .await?
+        .json()?;
+
+    Ok(token.map(|token| token.owner_id.to_string()))
+}
+
 pub async fn mint_nft(
     minter: &Account,
     contract_id: &AccountId,
@@ -131,3 +195,18 @@ pub async fn mint_nft(
 
     Ok(())
 }
+
+/// Collects the entries of every `event` emitted by the contract's own
+/// NEP-297 standard (see `nft_contract_tools::events`) during `res`.
+pub fn contract_events<T: DeserializeOwned>(res: &ExecutionFinalResult, event: &str) -> Vec<T> {
+    res.logs()
+        .into_iter()
+        .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+        .filter_map(|log| serde_json::from_str::<Value>(log).ok())
+        .filter(|log| log["standard"] == "my_nft_contract" && log["event"] == event)
+        .flat_map(|log| {
+            serde_json::from_value::<Vec<T>>(log["data"].clone())
+                .expect("Could not parse event data")
+        })
+        .collect()
+}
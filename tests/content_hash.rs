@@ -0,0 +1,81 @@
+pub mod common;
+
+use near_sdk::serde_json::json;
+use near_sdk_contract_tools::nft::{Token, TokenMetadata};
+use near_workspaces::{Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::content_hash::{self, VerifyError};
+use std::path::PathBuf;
+
+const TOKEN_ID: &str = "id-0";
+
+fn fixture(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", name]
+        .iter()
+        .collect()
+}
+
+#[tokio::test]
+async fn test_media_hash_round_trips_through_mint() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let media = fixture("token.svg");
+    let metadata = content_hash::with_media_file(
+        TokenMetadata::new().title("Hashed"),
+        "ipfs://bafy/token.svg",
+        &media,
+    )?;
+    assert_eq!(
+        metadata.media_hash.as_deref(),
+        Some(content_hash::hash_bytes(&std::fs::read(&media)?).as_str())
+    );
+
+    // The contract accepts the hash as a well-formed NEP-177 hash.
+    let res = nft_contract
+        .call("set_metadata_policy")
+        .args_json(json!({
+            "policy": {
+                "required_fields": ["media_hash"],
+                "max_lengths": {},
+                "url_schemes": [],
+                "check_hashes": true,
+                "extra_json": false,
+            },
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = nft_contract
+        .call("nft_mint")
+        .args_json(json!({"token_id": TOKEN_ID, "metadata": metadata}))
+        .max_gas()
+        .deposit(NearToken::from_millinear(21))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let token: Token = nft_contract
+        .call("nft_token")
+        .args_json(json!({"token_id": TOKEN_ID}))
+        .view()
+        .await?
+        .json()?;
+
+    content_hash::verify_media_hash(&token, &media)?;
+
+    let other = std::env::temp_dir().join("content_hash_other.svg");
+    std::fs::write(&other, "<svg/>")?;
+    assert!(matches!(
+        content_hash::verify_media_hash(&token, &other),
+        Err(VerifyError::Mismatch { .. })
+    ));
+
+    assert!(matches!(
+        content_hash::verify_reference_hash(&token, &media),
+        Err(VerifyError::MissingHash)
+    ));
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 /*!
-A stub contract that implements nft_on_transfer for simulation testing nft_transfer_call.
+A stub contract that implements nft_on_transfer and nft_on_batch_transfer for simulation testing
+nft_transfer_call and nft_batch_transfer_call.
 */
 use near_contract_standards::non_fungible_token::core::NonFungibleTokenReceiver;
 use near_contract_standards::non_fungible_token::TokenId;
@@ -15,6 +16,17 @@ pub struct TokenReceiver {}
 // Have to repeat the same trait for our own implementation.
 pub trait ValueReturnTrait {
     fn ok_go(&self, return_it: bool) -> PromiseOrValue<bool>;
+    fn ok_go_batch(&self, return_them: Vec<bool>) -> PromiseOrValue<Vec<bool>>;
+}
+
+pub trait NonFungibleTokenBatchReceiver {
+    fn nft_on_batch_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<bool>>;
 }
 
 #[near]
@@ -65,10 +77,60 @@ impl NonFungibleTokenReceiver for TokenReceiver {
     }
 }
 
+#[near]
+impl NonFungibleTokenBatchReceiver for TokenReceiver {
+    /// Returns, per token, true if it should be returned to its previous owner
+    /// Supported `msg`s:
+    /// * "return-it-now" / "keep-it-now" - immediately return or keep all tokens
+    /// * "return-it-later" / "keep-it-later" - same, resolved by a cross-contract call
+    /// * "return-first-now" - immediately return the first token and keep the rest
+    /// * "return-first-later" - same, resolved by a cross-contract call
+    /// Otherwise panics, which should also return all tokens to their previous owners
+    fn nft_on_batch_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<bool>> {
+        log!(
+            "in nft_on_batch_transfer; sender_id={}, previous_owner_ids={:?}, token_ids={:?}, msg={}",
+            &sender_id,
+            &previous_owner_ids,
+            &token_ids,
+            msg
+        );
+        let (return_them, later) = match msg.as_str() {
+            "return-it-now" => (vec![true; token_ids.len()], false),
+            "return-it-later" => (vec![true; token_ids.len()], true),
+            "keep-it-now" => (vec![false; token_ids.len()], false),
+            "keep-it-later" => (vec![false; token_ids.len()], true),
+            "return-first-now" => ((0..token_ids.len()).map(|i| i == 0).collect(), false),
+            "return-first-later" => ((0..token_ids.len()).map(|i| i == 0).collect(), true),
+            _ => env::panic_str("unsupported msg"),
+        };
+        if later {
+            let prepaid_gas = env::prepaid_gas();
+            let account_id = env::current_account_id();
+            Self::ext(account_id)
+                .with_static_gas(prepaid_gas.saturating_sub(GAS_FOR_NFT_ON_TRANSFER))
+                .ok_go_batch(return_them)
+                .into()
+        } else {
+            PromiseOrValue::Value(return_them)
+        }
+    }
+}
+
 #[near]
 impl ValueReturnTrait for TokenReceiver {
     fn ok_go(&self, return_it: bool) -> PromiseOrValue<bool> {
         log!("in ok_go, return_it={}", return_it);
         PromiseOrValue::Value(return_it)
     }
+
+    fn ok_go_batch(&self, return_them: Vec<bool>) -> PromiseOrValue<Vec<bool>> {
+        log!("in ok_go_batch, return_them={:?}", return_them);
+        PromiseOrValue::Value(return_them)
+    }
 }
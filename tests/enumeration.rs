@@ -248,7 +248,12 @@ async fn test_enum_nft_tokens_for_owner() -> anyhow::Result<()> {
     // Get tokens from account with no NFTs
     let owner_tokens: Vec<Token> = nft_contract
         .call("nft_tokens_for_owner")
-        .args_json((alice.id(), Option::<U128>::None, Option::<u64>::None))
+        .args_json((
+            alice.id(),
+            Option::<U128>::None,
+            Option::<u64>::None,
+            Option::<bool>::None,
+        ))
         .view()
         .await?
         .json()?;
@@ -257,7 +262,12 @@ async fn test_enum_nft_tokens_for_owner() -> anyhow::Result<()> {
     // Get tokens with no optional args
     let owner_tokens: Vec<Token> = nft_contract
         .call("nft_tokens_for_owner")
-        .args_json((nft_contract.id(), Option::<U128>::None, Option::<u64>::None))
+        .args_json((
+            nft_contract.id(),
+            Option::<U128>::None,
+            Option::<u64>::None,
+            Option::<bool>::None,
+        ))
         .view()
         .await?
         .json()?;
@@ -266,7 +276,12 @@ async fn test_enum_nft_tokens_for_owner() -> anyhow::Result<()> {
     // With from_index and no limit
     let owner_tokens: Vec<Token> = nft_contract
         .call("nft_tokens_for_owner")
-        .args_json((nft_contract.id(), Some(U128::from(2)), Option::<u64>::None))
+        .args_json((
+            nft_contract.id(),
+            Some(U128::from(2)),
+            Option::<u64>::None,
+            Option::<bool>::None,
+        ))
         .view()
         .await?
         .json()?;
@@ -277,7 +292,12 @@ async fn test_enum_nft_tokens_for_owner() -> anyhow::Result<()> {
     // With from_index and limit 1
     let owner_tokens: Vec<Token> = nft_contract
         .call("nft_tokens_for_owner")
-        .args_json((nft_contract.id(), Some(U128::from(1)), Some(1u64)))
+        .args_json((
+            nft_contract.id(),
+            Some(U128::from(1)),
+            Some(1u64),
+            Option::<bool>::None,
+        ))
         .view()
         .await?
         .json()?;
@@ -287,7 +307,12 @@ async fn test_enum_nft_tokens_for_owner() -> anyhow::Result<()> {
     // No from_index but limit 3
     let owner_tokens: Vec<Token> = nft_contract
         .call("nft_tokens_for_owner")
-        .args_json((nft_contract.id(), Option::<U128>::None, Some(3u64)))
+        .args_json((
+            nft_contract.id(),
+            Option::<U128>::None,
+            Some(3u64),
+            Option::<bool>::None,
+        ))
         .view()
         .await?
         .json()?;
@@ -0,0 +1,152 @@
+pub mod common;
+
+use near_sdk::serde_json::json;
+use near_workspaces::{Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::events::{
+    NftApproveLog, NftRevokeAllLog, NftRevokeLog, RedeemRecipeLog, StorageDepositLog,
+};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const TOKEN_ID: &str = "0";
+
+#[tokio::test]
+async fn test_mint_emits_storage_deposit_event() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let mint = |token_id: &'static str| {
+        nft_contract
+            .call("nft_mint")
+            .args_json(json!({
+                "token_id": token_id,
+                "metadata": {"title": "Event"},
+                "owner_id": alice.id(),
+            }))
+            .max_gas()
+            .deposit(NearToken::from_millinear(21))
+            .transact()
+    };
+
+    let res = mint("id-0").await?;
+    assert!(res.is_success());
+
+    let deposits: Vec<StorageDepositLog> = common::contract_events(&res, "storage_deposit");
+    assert_eq!(deposits.len(), 1);
+    assert_eq!(deposits[0].account_id.as_str(), alice.id().as_str());
+    assert_eq!(
+        deposits[0].amount.as_yoctonear(),
+        NearToken::from_millinear(21).as_yoctonear()
+    );
+
+    // Alice is registered now, so no more storage is deposited.
+    let res = mint("id-1").await?;
+    assert!(res.is_success());
+    assert!(common::contract_events::<StorageDepositLog>(&res, "storage_deposit").is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_approval_changes_emit_events() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(nft_contract.id()),
+    )
+    .await?;
+
+    for (account, approval_id) in [(&alice, 0), (&bob, 1)] {
+        let res = nft_contract
+            .call("nft_approve")
+            .args_json((TOKEN_ID, account.id(), Option::<String>::None))
+            .max_gas()
+            .deposit(NearToken::from_yoctonear(550000000000000000000))
+            .transact()
+            .await?;
+        assert!(res.is_success());
+
+        let approvals: Vec<NftApproveLog> = common::contract_events(&res, "nft_approve");
+        assert_eq!(approvals.len(), 1);
+        assert_eq!(approvals[0].token_id, TOKEN_ID);
+        assert_eq!(approvals[0].owner_id.as_str(), nft_contract.id().as_str());
+        assert_eq!(approvals[0].account_id.as_str(), account.id().as_str());
+        assert_eq!(approvals[0].approval_id, approval_id);
+    }
+
+    let res = nft_contract
+        .call("nft_revoke")
+        .args_json((TOKEN_ID, alice.id()))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let revokes: Vec<NftRevokeLog> = common::contract_events(&res, "nft_revoke");
+    assert_eq!(revokes.len(), 1);
+    assert_eq!(revokes[0].account_id.as_str(), alice.id().as_str());
+
+    let res = nft_contract
+        .call("nft_revoke_all")
+        .args_json((TOKEN_ID,))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let revokes: Vec<NftRevokeAllLog> = common::contract_events(&res, "nft_revoke_all");
+    assert_eq!(revokes.len(), 1);
+    assert_eq!(revokes[0].token_id, TOKEN_ID);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_actions_emit_events() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = nft_contract
+        .call("add_redeem_recipe")
+        .args_json(json!({
+            "recipe_id": "upgrade",
+            "recipe": {
+                "inputs": [{"token_id": "seed-0"}],
+                "output_metadata": {"title": "Upgraded"},
+                "output_token_id_prefix": "upgrade-",
+                "max_redemptions": null,
+                "starts_at": null,
+                "ends_at": null,
+            },
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let recipes: Vec<RedeemRecipeLog> = common::contract_events(&res, "redeem_recipe_add");
+    assert_eq!(recipes.len(), 1);
+    assert_eq!(recipes[0].recipe_id, "upgrade");
+
+    let res = nft_contract
+        .call("remove_redeem_recipe")
+        .args_json(json!({"recipe_id": "upgrade"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let recipes: Vec<RedeemRecipeLog> = common::contract_events(&res, "redeem_recipe_remove");
+    assert_eq!(recipes.len(), 1);
+    assert_eq!(recipes[0].recipe_id, "upgrade");
+
+    Ok(())
+}
@@ -0,0 +1,230 @@
+pub mod common;
+
+use near_sdk::serde_json::json;
+use near_workspaces::{Account, Contract, Worker, network::Sandbox, types::NearToken};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const FEE: NearToken = NearToken::from_millinear(10);
+
+async fn set_fee_policy(nft_contract: &Contract, beneficiary: &Account) -> anyhow::Result<()> {
+    let res = nft_contract
+        .call("set_fee_policy")
+        .args_json(json!({
+            "policy": {
+                "beneficiary_id": beneficiary.id(),
+                "flat": FEE,
+                "basis_points": 0,
+                "exempt_approved_accounts": [],
+                "exempt_owner_transfers": true,
+            },
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+async fn fee_balance_of(nft_contract: &Contract, account: &Account) -> anyhow::Result<NearToken> {
+    Ok(nft_contract
+        .call("fee_balance_of")
+        .args_json(json!({"account_id": account.id()}))
+        .view()
+        .await?
+        .json()?)
+}
+
+#[tokio::test]
+async fn test_transfer_fee_is_collected_and_withdrawn() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let charlie = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "id-0".into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+    set_fee_policy(&nft_contract, &charlie).await?;
+
+    // `nft_transfer` only takes 1 yoctoNEAR, which does not cover the fee.
+    let res = alice
+        .call(nft_contract.id(), "nft_transfer")
+        .args_json((
+            bob.id(),
+            "id-0",
+            Option::<u64>::None,
+            Option::<String>::None,
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("must be attached to nft_transfer_paid"));
+
+    let res = alice
+        .call(nft_contract.id(), "nft_transfer_paid")
+        .args_json(json!({"receiver_id": bob.id(), "token_id": "id-0"}))
+        .max_gas()
+        .deposit(FEE)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(fee_balance_of(&nft_contract, &charlie).await?, FEE);
+
+    let res = charlie
+        .call(nft_contract.id(), "withdraw_fees")
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Requires attached deposit of exactly 1 yoctoNEAR"));
+
+    let res = charlie
+        .call(nft_contract.id(), "withdraw_fees")
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(fee_balance_of(&nft_contract, &charlie).await?.is_zero());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_fee_withdrawal_is_credited_back() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "id-0".into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+    set_fee_policy(&nft_contract, nft_contract.as_account()).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "nft_transfer_paid")
+        .args_json(json!({"receiver_id": bob.id(), "token_id": "id-0"}))
+        .max_gas()
+        .deposit(FEE)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(
+        fee_balance_of(&nft_contract, nft_contract.as_account()).await?,
+        FEE
+    );
+
+    // The receiver does not exist, so the transfer fails.
+    let res = nft_contract
+        .call("withdraw_treasury_fees")
+        .args_json(json!({"receiver_id": format!("missing.{}", nft_contract.id())}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(!res.json::<bool>()?);
+    assert_eq!(
+        fee_balance_of(&nft_contract, nft_contract.as_account()).await?,
+        FEE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_owner_transfers_can_be_exempt() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let charlie = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "id-0".into(),
+        Some(nft_contract.id()),
+    )
+    .await?;
+    common::register_user(&nft_contract, alice.id()).await?;
+    set_fee_policy(&nft_contract, &charlie).await?;
+
+    let res = nft_contract
+        .call("nft_transfer")
+        .args_json((
+            alice.id(),
+            "id-0",
+            Option::<u64>::None,
+            Option::<String>::None,
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(fee_balance_of(&nft_contract, &charlie).await?.is_zero());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_percentage_fee_is_not_truncated() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let charlie = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "id-0".into(),
+        Some(alice.id()),
+    )
+    .await?;
+
+    let res = nft_contract
+        .call("set_fee_policy")
+        .args_json(json!({
+            "policy": {
+                "beneficiary_id": charlie.id(),
+                "flat": NearToken::from_yoctonear(0),
+                "basis_points": 250,
+                "exempt_approved_accounts": [],
+                "exempt_owner_transfers": false,
+            },
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let fee_at = |price: u128| {
+        nft_contract
+            .call("nft_transfer_fee")
+            .args_json(json!({"token_id": "id-0", "price": NearToken::from_yoctonear(price)}))
+            .view()
+    };
+
+    // 2.5% of 9999 yoctoNEAR, rounded down.
+    let fee: NearToken = fee_at(9_999).await?.json()?;
+    assert_eq!(fee, NearToken::from_yoctonear(249));
+
+    let fee: NearToken = fee_at(u128::MAX).await?.json()?;
+    assert_eq!(fee, NearToken::from_yoctonear(u128::MAX / 10_000 * 250));
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_workspaces::{Account, Contract, Worker, network::Sandbox, types::NearToken};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const TOKEN_ID: &str = "0";
+
+async fn transfer(
+    sender: &Account,
+    nft_contract: &Contract,
+    receiver: &Account,
+    memo: &str,
+) -> anyhow::Result<()> {
+    let res = sender
+        .call(nft_contract.id(), "nft_transfer")
+        .args_json((receiver.id(), TOKEN_ID, Option::<u64>::None, Some(memo)))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+async fn history(nft_contract: &Contract, args: Value) -> anyhow::Result<Vec<Value>> {
+    Ok(nft_contract
+        .call("nft_history")
+        .args_json(args)
+        .view()
+        .await?
+        .json()?)
+}
+
+#[tokio::test]
+async fn test_history_keeps_most_recent_transfers() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+
+    // History is off by default.
+    transfer(&alice, &nft_contract, &bob, "first").await?;
+    assert!(
+        history(&nft_contract, json!({"token_id": TOKEN_ID}))
+            .await?
+            .is_empty()
+    );
+
+    let res = nft_contract
+        .call("set_history_retention")
+        .args_json(json!({"retention": 2}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    transfer(&bob, &nft_contract, &alice, "second").await?;
+    transfer(&alice, &nft_contract, &bob, "third").await?;
+    transfer(&bob, &nft_contract, &alice, "fourth").await?;
+
+    let records = history(&nft_contract, json!({"token_id": TOKEN_ID})).await?;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["from"], json!(alice.id()));
+    assert_eq!(records[0]["to"], json!(bob.id()));
+    assert_eq!(records[0]["memo"], "third");
+    assert_eq!(records[0]["transfer_call"], false);
+    assert_eq!(records[1]["from"], json!(bob.id()));
+    assert_eq!(records[1]["memo"], "fourth");
+
+    let records = history(
+        &nft_contract,
+        json!({"token_id": TOKEN_ID, "from_index": "1", "limit": 1}),
+    )
+    .await?;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["memo"], "fourth");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_owner_can_set_history_retention() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "set_history_retention")
+        .args_json(json!({"retention": 10}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let retention: u32 = nft_contract
+        .call("history_retention")
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(retention, 0);
+
+    Ok(())
+}
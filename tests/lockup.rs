@@ -0,0 +1,153 @@
+pub mod common;
+
+use near_sdk::{
+    json_types::U64,
+    serde_json::{Value, json},
+};
+use near_workspaces::{
+    Account, Contract, Worker, network::Sandbox, result::ExecutionFinalResult, types::NearToken,
+};
+use nft_contract_tools::events::TokenLockupOverrideLog;
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const TOKEN_ID: &str = "0";
+
+async fn set_collection_lockup(nft_contract: &Contract, policy: Value) -> anyhow::Result<()> {
+    let res = nft_contract
+        .call("set_collection_lockup")
+        .args_json(json!({ "policy": policy }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+async fn transfer(
+    sender: &Account,
+    nft_contract: &Contract,
+    receiver: &Account,
+) -> anyhow::Result<ExecutionFinalResult> {
+    Ok(sender
+        .call(nft_contract.id(), "nft_transfer")
+        .args_json((
+            receiver.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?)
+}
+
+#[tokio::test]
+async fn test_cooldown_blocks_transfers_until_lifted() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    set_collection_lockup(&nft_contract, json!({"cooldown_seconds": 3600})).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+
+    let transferable_at: Option<U64> = nft_contract
+        .call("nft_transferable_at")
+        .args_json((TOKEN_ID,))
+        .view()
+        .await?
+        .json()?;
+    let now_ms = worker.view_block().await?.timestamp() / 1_000_000;
+    assert!(transferable_at.unwrap().0 > now_ms + 3_000_000);
+
+    let err = transfer(&alice, &nft_contract, &bob)
+        .await?
+        .into_result()
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("Token is locked until"));
+
+    // The owner lifts the lockup.
+    let res = nft_contract
+        .call("set_token_lockup")
+        .args_json(json!({"token_id": TOKEN_ID, "policy": null}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let overrides: Vec<TokenLockupOverrideLog> =
+        common::contract_events(&res, "token_lockup_override");
+    assert_eq!(overrides.len(), 1);
+    assert!(overrides[0].policy.is_none());
+
+    assert!(transfer(&alice, &nft_contract, &bob).await?.is_success());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expired_lockup_allows_transfers() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    set_collection_lockup(&nft_contract, json!({"locked_until": "1"})).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+
+    assert!(transfer(&alice, &nft_contract, &bob).await?.is_success());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_owner_can_override_lockups() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    set_collection_lockup(&nft_contract, json!({"cooldown_seconds": 3600})).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+
+    let res = alice
+        .call(nft_contract.id(), "set_token_lockup")
+        .args_json(json!({"token_id": TOKEN_ID, "policy": null}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let lockup: Option<Value> = nft_contract
+        .call("token_lockup")
+        .args_json((TOKEN_ID,))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(lockup.unwrap()["policy"]["cooldown_seconds"], 3600);
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_workspaces::{Contract, Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::events::MetadataPolicyUpdateLog;
+
+// Base64 of 32 bytes.
+const HASH: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+async fn mint(
+    nft_contract: &Contract,
+    token_id: &str,
+    metadata: Value,
+) -> anyhow::Result<near_workspaces::result::ExecutionFinalResult> {
+    Ok(nft_contract
+        .call("nft_mint")
+        .args_json(json!({"token_id": token_id, "metadata": metadata}))
+        .max_gas()
+        .deposit(NearToken::from_millinear(21))
+        .transact()
+        .await?)
+}
+
+async fn assert_mint_fails(
+    nft_contract: &Contract,
+    metadata: Value,
+    message: &str,
+) -> anyhow::Result<()> {
+    let err = mint(nft_contract, "invalid", metadata)
+        .await?
+        .into_result()
+        .unwrap_err();
+    assert!(format!("{err:?}").contains(message), "{err:?}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_metadata_policy_rejects_invalid_metadata() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    // Any metadata is accepted by default.
+    assert!(mint(&nft_contract, "id-0", json!({})).await?.is_success());
+
+    let res = nft_contract
+        .call("set_metadata_policy")
+        .args_json(json!({
+            "policy": {
+                "required_fields": ["title", "media"],
+                "max_lengths": {"title": 20, "extra": 32},
+                "url_schemes": ["https", "ipfs"],
+                "check_hashes": true,
+                "extra_json": true,
+            },
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let updates: Vec<MetadataPolicyUpdateLog> =
+        common::contract_events(&res, "metadata_policy_update");
+    assert_eq!(updates.len(), 1);
+
+    let valid = json!({
+        "title": "Valid",
+        "media": "ipfs://bafy",
+        "media_hash": HASH,
+        "extra": "{\"level\":1}",
+    });
+
+    let mut metadata = valid.clone();
+    metadata["title"] = json!("");
+    assert_mint_fails(&nft_contract, metadata, "Metadata `title` is required").await?;
+
+    let mut metadata = valid.clone();
+    metadata["media"] = Value::Null;
+    assert_mint_fails(&nft_contract, metadata, "Metadata `media` is required").await?;
+
+    let mut metadata = valid.clone();
+    metadata["title"] = json!("x".repeat(21));
+    assert_mint_fails(
+        &nft_contract,
+        metadata,
+        "Metadata `title` is longer than 20 bytes",
+    )
+    .await?;
+
+    let mut metadata = valid.clone();
+    metadata["media"] = json!("http://example.com/image.png");
+    assert_mint_fails(
+        &nft_contract,
+        metadata,
+        "Metadata `media` must be a URL with one of the schemes: https, ipfs",
+    )
+    .await?;
+
+    let mut metadata = valid.clone();
+    metadata["reference"] = json!("example.com/reference.json");
+    assert_mint_fails(
+        &nft_contract,
+        metadata,
+        "Metadata `reference` must be a URL with one of the schemes",
+    )
+    .await?;
+
+    let mut metadata = valid.clone();
+    metadata["media_hash"] = json!("AAAA");
+    assert_mint_fails(
+        &nft_contract,
+        metadata,
+        "Metadata `media_hash` must be the base64 encoding of 32 bytes",
+    )
+    .await?;
+
+    let mut metadata = valid.clone();
+    metadata["extra"] = json!("{not json");
+    assert_mint_fails(
+        &nft_contract,
+        metadata,
+        "Metadata `extra` must be valid JSON",
+    )
+    .await?;
+
+    assert!(mint(&nft_contract, "id-1", valid).await?.is_success());
+
+    // Updates follow the same rules.
+    let res = nft_contract
+        .call("nft_set_token_metadata")
+        .args_json(json!({"token_id": "id-0", "metadata": {"title": "No media"}}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Metadata `media` is required"));
+
+    Ok(())
+}
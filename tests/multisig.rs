@@ -0,0 +1,223 @@
+pub mod common;
+
+use near_sdk::{
+    json_types::Base64VecU8,
+    serde_json::{Value, json},
+};
+use near_workspaces::{Account, Contract, Worker, network::Sandbox, types::NearToken};
+
+async fn init_multisig_contract(
+    worker: &Worker<Sandbox>,
+    admins: &[&Account],
+    threshold: u32,
+) -> anyhow::Result<Contract> {
+    let nft_contract = common::deploy_nft_contract(worker).await?;
+
+    let admins = admins.iter().map(|admin| admin.id()).collect::<Vec<_>>();
+    let res = nft_contract
+        .call("new")
+        .args_json(json!({
+            "owner_id": nft_contract.id(),
+            "metadata": common::contract_metadata(),
+            "multisig": {"admins": admins, "threshold": threshold},
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(nft_contract)
+}
+
+fn set_access_mode_call(mode: &str) -> Value {
+    let args = json!({ "mode": mode }).to_string();
+
+    json!({
+        "method_name": "set_access_mode",
+        "args": Base64VecU8::from(args.into_bytes()),
+        "deposit": NearToken::from_yoctonear(0),
+    })
+}
+
+async fn access_mode(nft_contract: &Contract) -> anyhow::Result<String> {
+    Ok(nft_contract.call("access_mode").view().await?.json()?)
+}
+
+#[tokio::test]
+async fn test_multisig_executes_confirmed_proposal() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let charlie = worker.dev_create_account().await?;
+    let nft_contract = init_multisig_contract(&worker, &[&alice, &bob, &charlie], 2).await?;
+
+    // Admins are not owners themselves.
+    let res = alice
+        .call(nft_contract.id(), "set_access_mode")
+        .args_json(json!({"mode": "blocklist"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = alice
+        .call(nft_contract.id(), "multisig_propose")
+        .args_json(json!({"call": set_access_mode_call("blocklist")}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let proposal_id = res.json::<u64>()?;
+
+    let res = alice
+        .call(nft_contract.id(), "multisig_execute")
+        .args_json(json!({"proposal_id": proposal_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Proposal does not have enough confirmations"));
+
+    let res = bob
+        .call(nft_contract.id(), "multisig_confirm")
+        .args_json(json!({"proposal_id": proposal_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = charlie
+        .call(nft_contract.id(), "multisig_execute")
+        .args_json(json!({"proposal_id": proposal_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    assert_eq!(access_mode(&nft_contract).await?, "blocklist");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_admins_can_propose() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let mallory = worker.dev_create_account().await?;
+    let nft_contract = init_multisig_contract(&worker, &[&alice], 1).await?;
+
+    let res = mallory
+        .call(nft_contract.id(), "multisig_propose")
+        .args_json(json!({"call": set_access_mode_call("blocklist")}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Only multisig admins can do this"));
+
+    // The multisig config can only be changed through the multisig.
+    let res = alice
+        .call(nft_contract.id(), "set_multisig_config")
+        .args_json(json!({"config": {"admins": [mallory.id()], "threshold": 1}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    assert_eq!(access_mode(&nft_contract).await?, "open");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multisig_config_is_validated() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let nft_contract = common::deploy_nft_contract(&worker).await?;
+
+    let res = nft_contract
+        .call("new")
+        .args_json(json!({
+            "owner_id": nft_contract.id(),
+            "metadata": common::contract_metadata(),
+            "multisig": {"admins": [alice.id()], "threshold": 2},
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Multisig threshold cannot exceed the number of admins"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multisig_only_calls_admin_methods() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let nft_contract = init_multisig_contract(&worker, &[&alice], 1).await?;
+
+    // Private callbacks and methods any account can call are both rejected.
+    for (method_name, args) in [
+        (
+            "resolve_release",
+            json!({"source": {"contract_id": alice.id(), "token_id": "id-0"}, "owner_id": alice.id()}),
+        ),
+        (
+            "nft_transfer",
+            json!({"receiver_id": alice.id(), "token_id": "id-0"}),
+        ),
+    ] {
+        let res = alice
+            .call(nft_contract.id(), "multisig_propose")
+            .args_json(json!({"call": {
+                "method_name": method_name,
+                "args": Base64VecU8::from(args.to_string().into_bytes()),
+                "deposit": NearToken::from_yoctonear(0),
+            }}))
+            .max_gas()
+            .transact()
+            .await?;
+        let err = res.into_result().unwrap_err();
+        assert!(format!("{err:?}").contains("cannot be called through the multisig"));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_call_keeps_proposal() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let nft_contract = init_multisig_contract(&worker, &[&alice], 1).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "multisig_propose")
+        .args_json(json!({"call": set_access_mode_call("not_a_mode")}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let proposal_id = res.json::<u64>()?;
+
+    let res = alice
+        .call(nft_contract.id(), "multisig_execute")
+        .args_json(json!({"proposal_id": proposal_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    let failures: Vec<Value> = common::contract_events(&res, "multisig_execute_failure");
+    assert_eq!(failures, vec![json!({"proposal_id": proposal_id})]);
+
+    let proposal: Option<Value> = nft_contract
+        .call("multisig_proposal")
+        .args_json(json!({"proposal_id": proposal_id}))
+        .view()
+        .await?
+        .json()?;
+    let proposal = proposal.expect("proposal should be kept");
+    assert_eq!(proposal["executing"], json!(false));
+    assert_eq!(access_mode(&nft_contract).await?, "open");
+
+    Ok(())
+}
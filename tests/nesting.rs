@@ -0,0 +1,375 @@
+pub mod common;
+
+use near_sdk::AccountId;
+use near_sdk_contract_tools::nft::Token;
+use near_workspaces::{Worker, network::Sandbox, types::NearToken};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const PARENT_ID: &str = "character";
+const CHILD_ID: &str = "sword";
+const GRANDCHILD_ID: &str = "gem";
+
+#[tokio::test]
+async fn test_nest_and_view_children() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    for token_id in [PARENT_ID, CHILD_ID] {
+        common::mint_nft(
+            nft_contract.as_account(),
+            nft_contract.id(),
+            token_id.into(),
+            Some(alice.id()),
+        )
+        .await?;
+    }
+
+    let res = alice
+        .call(nft_contract.id(), "nft_nest")
+        .args_json((CHILD_ID, PARENT_ID))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let children: Vec<Token> = nft_contract
+        .call("nft_children")
+        .args_json((PARENT_ID,))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].token_id, CHILD_ID);
+    assert_eq!(
+        children[0].owner_id.to_string(),
+        nft_contract.id().to_string()
+    );
+
+    let parent: Option<String> = nft_contract
+        .call("nft_parent")
+        .args_json((CHILD_ID,))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(parent.as_deref(), Some(PARENT_ID));
+
+    let root_owner: Option<AccountId> = nft_contract
+        .call("nft_root_owner")
+        .args_json((CHILD_ID,))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(root_owner.unwrap().to_string(), alice.id().to_string());
+
+    let direct_tokens: Vec<Token> = nft_contract
+        .call("nft_tokens_for_owner")
+        .args_json((
+            alice.id(),
+            Option::<String>::None,
+            Option::<u32>::None,
+            Option::<bool>::None,
+        ))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(direct_tokens.len(), 1);
+    assert_eq!(direct_tokens[0].token_id, PARENT_ID);
+
+    let all_tokens: Vec<Token> = nft_contract
+        .call("nft_tokens_for_owner")
+        .args_json((
+            alice.id(),
+            Option::<String>::None,
+            Option::<u32>::None,
+            Some(true),
+        ))
+        .view()
+        .await?
+        .json()?;
+    let token_ids: Vec<&str> = all_tokens.iter().map(|t| t.token_id.as_str()).collect();
+    assert_eq!(token_ids, vec![PARENT_ID, CHILD_ID]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transfer_parent_moves_tree() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    for token_id in [PARENT_ID, CHILD_ID, GRANDCHILD_ID] {
+        common::mint_nft(
+            nft_contract.as_account(),
+            nft_contract.id(),
+            token_id.into(),
+            Some(alice.id()),
+        )
+        .await?;
+    }
+    common::register_user(&nft_contract, bob.id()).await?;
+
+    for (token_id, parent_token_id) in [(CHILD_ID, PARENT_ID), (GRANDCHILD_ID, CHILD_ID)] {
+        let res = alice
+            .call(nft_contract.id(), "nft_nest")
+            .args_json((token_id, parent_token_id))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    let res = alice
+        .call(nft_contract.id(), "nft_transfer")
+        .args_json((
+            bob.id(),
+            PARENT_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let root_owner: Option<AccountId> = nft_contract
+        .call("nft_root_owner")
+        .args_json((GRANDCHILD_ID,))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(root_owner.unwrap().to_string(), bob.id().to_string());
+
+    let bob_tokens: Vec<Token> = nft_contract
+        .call("nft_tokens_for_owner")
+        .args_json((
+            bob.id(),
+            Option::<String>::None,
+            Option::<u32>::None,
+            Some(true),
+        ))
+        .view()
+        .await?
+        .json()?;
+    let token_ids: Vec<&str> = bob_tokens.iter().map(|t| t.token_id.as_str()).collect();
+    assert_eq!(token_ids, vec![PARENT_ID, CHILD_ID, GRANDCHILD_ID]);
+
+    // Bob now controls the tree and can take the child out of it.
+    let res = bob
+        .call(nft_contract.id(), "nft_unnest")
+        .args_json((CHILD_ID,))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let token: Token = nft_contract
+        .call("nft_token")
+        .args_json((CHILD_ID,))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(token.owner_id.to_string(), bob.id().to_string());
+
+    let root_owner: Option<AccountId> = nft_contract
+        .call("nft_root_owner")
+        .args_json((GRANDCHILD_ID,))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(root_owner.unwrap().to_string(), bob.id().to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nest_rejects_cycles() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    for token_id in [PARENT_ID, CHILD_ID] {
+        common::mint_nft(
+            nft_contract.as_account(),
+            nft_contract.id(),
+            token_id.into(),
+            Some(alice.id()),
+        )
+        .await?;
+    }
+
+    let res = alice
+        .call(nft_contract.id(), "nft_nest")
+        .args_json((PARENT_ID, PARENT_ID))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = alice
+        .call(nft_contract.id(), "nft_nest")
+        .args_json((CHILD_ID, PARENT_ID))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // Nesting the parent under its own child would close a cycle.
+    let res = alice
+        .call(nft_contract.id(), "nft_nest")
+        .args_json((PARENT_ID, CHILD_ID))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let parent: Option<String> = nft_contract
+        .call("nft_parent")
+        .args_json((PARENT_ID,))
+        .view()
+        .await?
+        .json()?;
+    assert!(parent.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nested_token_cannot_be_transferred_or_burned() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    for token_id in [PARENT_ID, CHILD_ID] {
+        common::mint_nft(
+            nft_contract.as_account(),
+            nft_contract.id(),
+            token_id.into(),
+            Some(nft_contract.id()),
+        )
+        .await?;
+    }
+    common::register_user(&nft_contract, alice.id()).await?;
+
+    let res = nft_contract
+        .call("nft_nest")
+        .args_json((CHILD_ID, PARENT_ID))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // The contract account is the custodian of nested tokens, but still
+    // cannot move them out of the tree with a plain transfer.
+    let res = nft_contract
+        .call("nft_transfer")
+        .args_json((
+            alice.id(),
+            CHILD_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = nft_contract
+        .call("nft_burn")
+        .args_json((PARENT_ID,))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = nft_contract
+        .call("nft_burn")
+        .args_json((CHILD_ID,))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nesting_goes_through_transfer_checks() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    for token_id in [PARENT_ID, CHILD_ID] {
+        common::mint_nft(
+            nft_contract.as_account(),
+            nft_contract.id(),
+            token_id.into(),
+            Some(alice.id()),
+        )
+        .await?;
+    }
+
+    let res = nft_contract
+        .call("pause_contract")
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let nest = || {
+        alice
+            .call(nft_contract.id(), "nft_nest")
+            .args_json((CHILD_ID, PARENT_ID))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+    };
+
+    let err = nest().await?.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("paused"));
+
+    let res = nft_contract
+        .call("unpause_contract")
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = nest().await?;
+    assert!(res.is_success());
+    assert!(
+        res.logs().iter().any(
+            |log| log.contains(r#""event":"nft_transfer""#) && log.contains(r#""memo":"nest""#)
+        )
+    );
+
+    // The contract account holds the child for alice, but does not list it
+    // as its own.
+    let contract_tokens: Vec<Token> = nft_contract
+        .call("nft_tokens_for_owner")
+        .args_json((
+            nft_contract.id(),
+            Option::<String>::None,
+            Option::<u32>::None,
+            Option::<bool>::None,
+        ))
+        .view()
+        .await?
+        .json()?;
+    assert!(contract_tokens.is_empty());
+
+    Ok(())
+}
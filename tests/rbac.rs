@@ -0,0 +1,171 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_workspaces::{Account, Contract, Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::events::RoleLog;
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const TOKEN_ID: &str = "0";
+
+async fn role_members(nft_contract: &Contract, role: &str) -> anyhow::Result<Vec<String>> {
+    Ok(nft_contract
+        .call("role_members")
+        .args_json(json!({ "role": role }))
+        .view()
+        .await?
+        .json()?)
+}
+
+async fn mint_as(
+    minter: &Account,
+    nft_contract: &Contract,
+    token_id: &str,
+) -> anyhow::Result<near_workspaces::result::ExecutionFinalResult> {
+    Ok(minter
+        .call(nft_contract.id(), "nft_mint")
+        .args_json(json!({
+            "token_id": token_id,
+            "metadata": {"title": "Role"},
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(21))
+        .transact()
+        .await?)
+}
+
+#[tokio::test]
+async fn test_minter_role_is_granted_and_revoked() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let err = mint_as(&alice, &nft_contract, "id-0")
+        .await?
+        .into_result()
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("Only the owner or a Minter can do this"));
+
+    // Only the owner can grant roles.
+    let res = bob
+        .call(nft_contract.id(), "grant_role")
+        .args_json(json!({"account_id": bob.id(), "role": "minter"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = nft_contract
+        .call("grant_role")
+        .args_json(json!({"account_id": alice.id(), "role": "minter"}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let granted: Vec<RoleLog> = common::contract_events(&res, "role_grant");
+    assert_eq!(granted.len(), 1);
+    assert_eq!(granted[0].account_id.as_str(), alice.id().as_str());
+
+    assert_eq!(
+        role_members(&nft_contract, "minter").await?,
+        vec![alice.id().to_string()]
+    );
+    assert!(role_members(&nft_contract, "moderator").await?.is_empty());
+
+    assert!(mint_as(&alice, &nft_contract, "id-0").await?.is_success());
+
+    common::owner_call(
+        &nft_contract,
+        "revoke_role",
+        json!({"account_id": alice.id(), "role": "minter"}),
+    )
+    .await?;
+    assert!(role_members(&nft_contract, "minter").await?.is_empty());
+
+    assert!(mint_as(&alice, &nft_contract, "id-1").await?.is_failure());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pauser_and_moderator_roles() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&nft_contract, bob.id()).await?;
+
+    common::owner_call(
+        &nft_contract,
+        "grant_role",
+        json!({"account_id": bob.id(), "role": "pauser"}),
+    )
+    .await?;
+
+    // Pausing takes the pauser role, not just any role.
+    let res = alice
+        .call(nft_contract.id(), "pause_contract")
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = bob
+        .call(nft_contract.id(), "pause_contract")
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(nft_contract.id(), "nft_transfer")
+        .args_json(json!({"token_id": TOKEN_ID, "receiver_id": bob.id()}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    // Moderators can still burn while the contract is paused.
+    let res = bob
+        .call(nft_contract.id(), "nft_moderation_burn")
+        .args_json(json!({"token_id": TOKEN_ID}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Only the owner or a Moderator can do this"));
+
+    common::owner_call(
+        &nft_contract,
+        "grant_role",
+        json!({"account_id": bob.id(), "role": "moderator"}),
+    )
+    .await?;
+
+    let res = bob
+        .call(nft_contract.id(), "nft_moderation_burn")
+        .args_json(json!({"token_id": TOKEN_ID}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let token: Option<Value> = nft_contract
+        .call("nft_token")
+        .args_json(json!({"token_id": TOKEN_ID}))
+        .view()
+        .await?
+        .json()?;
+    assert!(token.is_none());
+
+    Ok(())
+}
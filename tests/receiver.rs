@@ -0,0 +1,190 @@
+pub mod common;
+
+use near_sdk::AccountId;
+use near_sdk::serde_json::json;
+use near_workspaces::{Contract, Worker, network::Sandbox, types::NearToken};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const TOKEN_ID: &str = "external-0";
+
+async fn accept_deposits_from(
+    nft_contract: &Contract,
+    external_contract: &Contract,
+) -> anyhow::Result<()> {
+    let res = nft_contract
+        .call("set_receiver_policy")
+        .args_json(json!({
+            "policy": {
+                "accept_any_contract": false,
+                "accepted_contracts": [external_contract.id()],
+                "allowed_actions": ["deposit"],
+            }
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_policy_returns_token() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+    let external_contract = common::init_nft_contract(&worker).await?;
+
+    common::mint_nft(
+        external_contract.as_account(),
+        external_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&external_contract, nft_contract.id()).await?;
+
+    let res = alice
+        .call(external_contract.id(), "nft_transfer_call")
+        .args_json((
+            nft_contract.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+            "deposit",
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    assert_eq!(
+        common::token_owner(&external_contract, TOKEN_ID).await?,
+        Some(alice.id().to_string())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_and_withdraw_external_token() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+    let external_contract = common::init_nft_contract(&worker).await?;
+
+    common::mint_nft(
+        external_contract.as_account(),
+        external_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&external_contract, nft_contract.id()).await?;
+    accept_deposits_from(&nft_contract, &external_contract).await?;
+
+    let res = alice
+        .call(external_contract.id(), "nft_transfer_call")
+        .args_json((
+            nft_contract.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+            "deposit",
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    assert_eq!(
+        common::token_owner(&external_contract, TOKEN_ID).await?,
+        Some(nft_contract.id().to_string())
+    );
+
+    let depositor: Option<AccountId> = nft_contract
+        .call("external_depositor_of")
+        .args_json((external_contract.id(), TOKEN_ID))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(depositor.unwrap().to_string(), alice.id().to_string());
+
+    // Only the depositor can take the token back out.
+    let res = bob
+        .call(nft_contract.id(), "nft_withdraw_external")
+        .args_json((external_contract.id(), TOKEN_ID))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = alice
+        .call(nft_contract.id(), "nft_withdraw_external")
+        .args_json((external_contract.id(), TOKEN_ID))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(res.json::<bool>()?);
+
+    assert_eq!(
+        common::token_owner(&external_contract, TOKEN_ID).await?,
+        Some(alice.id().to_string())
+    );
+
+    let deposits: Vec<near_sdk::serde_json::Value> = nft_contract
+        .call("external_deposits_of")
+        .args_json((alice.id(),))
+        .view()
+        .await?
+        .json()?;
+    assert!(deposits.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsupported_msg_returns_token() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+    let external_contract = common::init_nft_contract(&worker).await?;
+
+    common::mint_nft(
+        external_contract.as_account(),
+        external_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&external_contract, nft_contract.id()).await?;
+    accept_deposits_from(&nft_contract, &external_contract).await?;
+
+    let res = alice
+        .call(external_contract.id(), "nft_transfer_call")
+        .args_json((
+            nft_contract.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+            "do-something-else",
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    assert_eq!(
+        common::token_owner(&external_contract, TOKEN_ID).await?,
+        Some(alice.id().to_string())
+    );
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_sdk_contract_tools::nft::Token;
+use near_workspaces::{Account, Contract, Worker, network::Sandbox, types::NearToken};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const RECIPE_ID: &str = "upgrade";
+
+async fn add_recipe(nft_contract: &Contract, recipe: Value) -> anyhow::Result<()> {
+    let res = nft_contract
+        .call("add_redeem_recipe")
+        .args_json(json!({"recipe_id": RECIPE_ID, "recipe": recipe}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+fn upgrade_recipe(max_redemptions: Option<u64>, ends_at: Option<&str>) -> Value {
+    json!({
+        "inputs": [{"token_id": "seed-0"}, {"series": "shard"}],
+        "output_metadata": {"title": "Upgraded"},
+        "output_token_id_prefix": "upgrade-",
+        "max_redemptions": max_redemptions,
+        "starts_at": null,
+        "ends_at": ends_at,
+    })
+}
+
+async fn mint_inputs(nft_contract: &Contract, owner: &Account) -> anyhow::Result<()> {
+    for token_id in ["seed-0", "shard:1"] {
+        common::mint_nft(
+            nft_contract.as_account(),
+            nft_contract.id(),
+            token_id.into(),
+            Some(owner.id()),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn token_exists(nft_contract: &Contract, token_id: &str) -> anyhow::Result<bool> {
+    let token: Option<Token> = nft_contract
+        .call("nft_token")
+        .args_json((token_id,))
+        .view()
+        .await?
+        .json()?;
+
+    Ok(token.is_some())
+}
+
+#[tokio::test]
+async fn test_redeem_burns_inputs_and_mints_output() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    add_recipe(&nft_contract, upgrade_recipe(None, None)).await?;
+    mint_inputs(&nft_contract, &alice).await?;
+
+    let balance_before = common::storage_balance_of(&nft_contract, &alice)
+        .await?
+        .unwrap();
+
+    // The inputs free more storage than the output takes, so no deposit is
+    // needed.
+    let res = alice
+        .call(nft_contract.id(), "nft_redeem")
+        .args_json((RECIPE_ID, vec!["seed-0", "shard:1"]))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(res.json::<String>()?, "upgrade-1");
+
+    let balance_after = common::storage_balance_of(&nft_contract, &alice)
+        .await?
+        .unwrap();
+    assert_eq!(balance_after.total, balance_before.total);
+    assert!(balance_after.available > balance_before.available);
+
+    assert!(!token_exists(&nft_contract, "seed-0").await?);
+    assert!(!token_exists(&nft_contract, "shard:1").await?);
+
+    let token: Token = nft_contract
+        .call("nft_token")
+        .args_json(("upgrade-1",))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(token.owner_id.to_string(), alice.id().to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_redeem_respects_supply_limit() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    add_recipe(&nft_contract, upgrade_recipe(Some(1), None)).await?;
+    mint_inputs(&nft_contract, &alice).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "nft_redeem")
+        .args_json((RECIPE_ID, vec!["seed-0", "shard:1"]))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "shard:2".into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "seed-0".into(),
+        Some(alice.id()),
+    )
+    .await?;
+
+    let res = alice
+        .call(nft_contract.id(), "nft_redeem")
+        .args_json((RECIPE_ID, vec!["seed-0", "shard:2"]))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    assert!(token_exists(&nft_contract, "seed-0").await?);
+    assert!(token_exists(&nft_contract, "shard:2").await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_redeem_rejects_mismatched_inputs_and_closed_window() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    add_recipe(&nft_contract, upgrade_recipe(None, None)).await?;
+    mint_inputs(&nft_contract, &alice).await?;
+
+    // Inputs are matched by position.
+    let res = alice
+        .call(nft_contract.id(), "nft_redeem")
+        .args_json((RECIPE_ID, vec!["shard:1", "seed-0"]))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(token_exists(&nft_contract, "seed-0").await?);
+    assert!(token_exists(&nft_contract, "shard:1").await?);
+
+    let res = nft_contract
+        .call("remove_redeem_recipe")
+        .args_json((RECIPE_ID,))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    add_recipe(&nft_contract, upgrade_recipe(None, Some("1"))).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "nft_redeem")
+        .args_json((RECIPE_ID, vec!["seed-0", "shard:1"]))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(token_exists(&nft_contract, "seed-0").await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_owner_can_add_recipe() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "add_redeem_recipe")
+        .args_json(json!({"recipe_id": RECIPE_ID, "recipe": upgrade_recipe(None, None)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    Ok(())
+}
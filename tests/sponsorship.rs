@@ -0,0 +1,287 @@
+pub mod common;
+
+use near_sdk::serde_json::json;
+use near_workspaces::{Account, Contract, Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::events::StorageDepositLog;
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const MIN_STORAGE: NearToken = NearToken::from_millinear(7);
+
+async fn fund_pool(nft_contract: &Contract, amount: NearToken) -> anyhow::Result<()> {
+    let res = nft_contract
+        .call("fund_sponsor_pool")
+        .max_gas()
+        .deposit(amount)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+async fn pool_balance(nft_contract: &Contract) -> anyhow::Result<NearToken> {
+    Ok(nft_contract
+        .call("sponsor_pool_balance")
+        .view()
+        .await?
+        .json()?)
+}
+
+async fn mint_without_deposit(
+    nft_contract: &Contract,
+    token_id: &str,
+    owner: &Account,
+) -> anyhow::Result<near_workspaces::result::ExecutionFinalResult> {
+    Ok(nft_contract
+        .call("nft_mint")
+        .args_json(json!({
+            "token_id": token_id,
+            "metadata": {"title": "Sponsored"},
+            "owner_id": owner.id(),
+        }))
+        .max_gas()
+        .transact()
+        .await?)
+}
+
+#[tokio::test]
+async fn test_transfer_to_unregistered_account_is_sponsored() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "id-0".into(),
+        None,
+    )
+    .await?;
+
+    let transfer = || {
+        nft_contract
+            .call("nft_transfer")
+            .args_json(json!({"receiver_id": alice.id(), "token_id": "id-0"}))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+    };
+
+    // Nothing to sponsor from yet.
+    assert!(transfer().await?.is_failure());
+
+    // Only the owner funds the pool.
+    let res = alice
+        .call(nft_contract.id(), "fund_sponsor_pool")
+        .max_gas()
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    fund_pool(&nft_contract, NearToken::from_millinear(100)).await?;
+
+    let res = transfer().await?;
+    assert!(res.is_success());
+    let sponsored: Vec<StorageDepositLog> = common::contract_events(&res, "storage_sponsor");
+    assert_eq!(sponsored.len(), 1);
+    assert_eq!(sponsored[0].account_id.as_str(), alice.id().as_str());
+    assert_eq!(sponsored[0].amount, MIN_STORAGE);
+
+    let balance = common::storage_balance_of(&nft_contract, &alice)
+        .await?
+        .expect("alice should be registered");
+    assert_eq!(balance.total.as_yoctonear(), MIN_STORAGE.as_yoctonear());
+    assert_eq!(
+        pool_balance(&nft_contract).await?,
+        NearToken::from_millinear(93)
+    );
+
+    let accounts: Vec<near_sdk::serde_json::Value> = nft_contract
+        .call("sponsored_accounts")
+        .args_json(json!({}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(
+        accounts,
+        vec![json!({"account_id": alice.id(), "amount": MIN_STORAGE})]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sponsorship_caps() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    fund_pool(&nft_contract, NearToken::from_millinear(100)).await?;
+
+    let res = nft_contract
+        .call("set_sponsorship_caps")
+        .args_json(json!({"caps": {"per_account": MIN_STORAGE, "total": MIN_STORAGE}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // A mint without a deposit registers alice from the pool.
+    assert!(
+        mint_without_deposit(&nft_contract, "id-0", &alice)
+            .await?
+            .is_success()
+    );
+    assert!(
+        common::storage_balance_of(&nft_contract, &alice)
+            .await?
+            .is_some()
+    );
+
+    // The total cap is used up, so bob has to register first.
+    assert!(
+        mint_without_deposit(&nft_contract, "id-1", &bob)
+            .await?
+            .is_failure()
+    );
+    assert!(
+        common::storage_balance_of(&nft_contract, &bob)
+            .await?
+            .is_none()
+    );
+
+    let sponsored_total: NearToken = nft_contract.call("sponsored_total").view().await?.json()?;
+    assert_eq!(sponsored_total, MIN_STORAGE);
+
+    // What is left of the pool goes back to the owner.
+    let res = nft_contract
+        .call("withdraw_sponsor_pool")
+        .args_json(json!({}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(pool_balance(&nft_contract).await?.is_zero());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_per_account_cap_spans_registrations() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    fund_pool(&nft_contract, NearToken::from_millinear(100)).await?;
+
+    let res = nft_contract
+        .call("set_sponsorship_caps")
+        .args_json(json!({"caps": {"per_account": MIN_STORAGE, "total": null}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    assert!(
+        mint_without_deposit(&nft_contract, "id-0", &alice)
+            .await?
+            .is_success()
+    );
+
+    let res = alice
+        .call(nft_contract.id(), "storage_unregister")
+        .args_json(json!({"force": true}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(
+        pool_balance(&nft_contract).await?,
+        NearToken::from_millinear(100)
+    );
+
+    // The pool and the total cap would allow it, but alice has already been
+    // sponsored as much as one account can be.
+    assert!(
+        mint_without_deposit(&nft_contract, "id-1", &alice)
+            .await?
+            .is_failure()
+    );
+    assert!(
+        common::storage_balance_of(&nft_contract, &alice)
+            .await?
+            .is_none()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sponsored_storage_cannot_drain_pool() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    fund_pool(&nft_contract, NearToken::from_millinear(100)).await?;
+
+    assert!(
+        mint_without_deposit(&nft_contract, "id-0", &alice)
+            .await?
+            .is_success()
+    );
+    assert_eq!(
+        pool_balance(&nft_contract).await?,
+        NearToken::from_millinear(93)
+    );
+
+    // The sponsored balance cannot be withdrawn.
+    let res = alice
+        .call(nft_contract.id(), "storage_withdraw")
+        .args_json(json!({"amount": MIN_STORAGE}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Sponsored storage balance cannot be withdrawn"));
+
+    let res = alice
+        .call(nft_contract.id(), "storage_withdraw")
+        .args_json(json!({}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let balance = common::storage_balance_of(&nft_contract, &alice)
+        .await?
+        .expect("alice should be registered");
+    assert_eq!(balance.total.as_yoctonear(), MIN_STORAGE.as_yoctonear());
+
+    // Unregistering returns the sponsored balance to the pool instead of
+    // refunding it.
+    let res = alice
+        .call(nft_contract.id(), "storage_unregister")
+        .args_json(json!({"force": true}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let reclaimed: Vec<StorageDepositLog> =
+        common::contract_events(&res, "storage_sponsor_reclaim");
+    assert_eq!(reclaimed.len(), 1);
+    assert_eq!(reclaimed[0].amount, MIN_STORAGE);
+
+    assert_eq!(
+        pool_balance(&nft_contract).await?,
+        NearToken::from_millinear(100)
+    );
+    let sponsored_total: NearToken = nft_contract.call("sponsored_total").view().await?.json()?;
+    assert!(sponsored_total.is_zero());
+
+    Ok(())
+}
@@ -0,0 +1,312 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_sdk_contract_tools::ft::StorageBalanceBounds;
+use near_workspaces::{Contract, Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::events::StorageBoundsUpdateLog;
+
+async fn storage_bounds(nft_contract: &Contract) -> anyhow::Result<StorageBalanceBounds> {
+    Ok(nft_contract
+        .call("storage_balance_bounds")
+        .view()
+        .await?
+        .json()?)
+}
+
+fn bounds(min_millinear: u128, max_millinear: u128) -> Value {
+    json!({
+        "min": NearToken::from_millinear(min_millinear),
+        "max": NearToken::from_millinear(max_millinear),
+    })
+}
+
+#[tokio::test]
+async fn test_init_with_storage_bounds() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let nft_contract = common::deploy_nft_contract(&worker).await?;
+
+    let res = nft_contract
+        .call("new")
+        .args_json(json!({
+            "owner_id": nft_contract.id(),
+            "metadata": common::contract_metadata(),
+            "storage_balance_bounds": bounds(10, 30),
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let bounds = storage_bounds(&nft_contract).await?;
+    assert_eq!(
+        bounds.min.as_yoctonear(),
+        NearToken::from_millinear(10).as_yoctonear()
+    );
+    assert_eq!(
+        bounds.max.map(|max| max.as_yoctonear()),
+        Some(NearToken::from_millinear(30).as_yoctonear())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_owner_sets_storage_bounds() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "set_storage_bounds")
+        .args_json(json!({"bounds": bounds(10, 30)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = nft_contract
+        .call("set_storage_bounds")
+        .args_json(json!({"bounds": bounds(10, 5)}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Maximum storage balance cannot be below the minimum"));
+
+    let res = nft_contract
+        .call("set_storage_bounds")
+        .args_json(json!({"bounds": bounds(10, 30)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let updates: Vec<StorageBoundsUpdateLog> =
+        common::contract_events(&res, "storage_bounds_update");
+    assert_eq!(updates.len(), 1);
+    assert_eq!(
+        updates[0].bounds.min.as_yoctonear(),
+        NearToken::from_millinear(10).as_yoctonear()
+    );
+
+    // New registrations use the new minimum.
+    common::register_user(&nft_contract, alice.id()).await?;
+    assert_eq!(
+        common::storage_balance_of(&nft_contract, &alice)
+            .await?
+            .unwrap()
+            .total
+            .as_yoctonear(),
+        NearToken::from_millinear(10).as_yoctonear()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_minimum_covers_measured_token_storage() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "id-0".into(),
+        None,
+    )
+    .await?;
+
+    let usage: String = nft_contract
+        .call("token_storage_usage")
+        .view()
+        .await?
+        .json()?;
+    assert!(usage.parse::<u64>()? > 0);
+
+    let res = nft_contract
+        .call("set_storage_bounds")
+        .args_json(json!({"bounds": {"min": NearToken::from_yoctonear(1), "max": null}}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(
+        format!("{err:?}").contains("Minimum storage balance must cover the storage of one token")
+    );
+
+    // After a reset, the smallest possible token still bounds the minimum.
+    let res = nft_contract
+        .call("reset_token_storage_usage")
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let usage: String = nft_contract
+        .call("token_storage_usage")
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(usage, "0");
+
+    let res = nft_contract
+        .call("set_storage_bounds")
+        .args_json(json!({"bounds": {"min": NearToken::from_yoctonear(1), "max": null}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raised_minimum_tops_up_registered_accounts() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::register_user(&nft_contract, alice.id()).await?;
+
+    let res = nft_contract
+        .call("set_storage_bounds")
+        .args_json(json!({"bounds": bounds(10, 21)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // Alice's 0.007 NEAR is below the new minimum. Minting tops her up to the
+    // maximum instead of failing on the full attached deposit.
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "id-0".into(),
+        Some(alice.id()),
+    )
+    .await?;
+
+    assert_eq!(
+        common::storage_balance_of(&nft_contract, &alice)
+            .await?
+            .unwrap()
+            .total
+            .as_yoctonear(),
+        NearToken::from_millinear(21).as_yoctonear()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_storage_cost_estimate_matches_mint() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    // Leave room in alice's storage balance for large metadata.
+    let res = nft_contract
+        .call("set_storage_bounds")
+        .args_json(json!({"bounds": bounds(7, 100)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let res = nft_contract
+        .call("storage_deposit")
+        .args_json((alice.id(), Option::<bool>::None))
+        .max_gas()
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // The first mint to alice also creates her enumeration set, and the
+    // first token with a trait value also indexes the value.
+    for (token_id, extra_len, traits) in [
+        ("a", 0, json!(null)),
+        (
+            "token-with-a-longer-id",
+            100,
+            json!([{"key": "color", "value": "red"}]),
+        ),
+        (
+            "b",
+            1000,
+            json!([
+                {"key": "color", "value": "red"},
+                {"key": "size", "value": "large"},
+            ]),
+        ),
+    ] {
+        let metadata = json!({
+            "title": format!("Title for {token_id}"),
+            "extra": "x".repeat(extra_len),
+        });
+
+        let estimate: Value = nft_contract
+            .call("nft_storage_cost_estimate")
+            .args_json(json!({
+                "token_id": token_id,
+                "metadata": metadata,
+                "owner_id": alice.id(),
+                "traits": traits,
+            }))
+            .view()
+            .await?
+            .json()?;
+
+        let storage_before = worker.view_account(nft_contract.id()).await?.storage_usage;
+
+        let res = nft_contract
+            .call("nft_mint")
+            .args_json(json!({
+                "token_id": token_id,
+                "metadata": metadata,
+                "owner_id": alice.id(),
+                "traits": traits,
+            }))
+            .max_gas()
+            .deposit(NearToken::from_millinear(21))
+            .transact()
+            .await?;
+        assert!(res.is_success());
+
+        let storage_after = worker.view_account(nft_contract.id()).await?.storage_usage;
+
+        assert_eq!(
+            estimate["bytes"],
+            json!((storage_after - storage_before).to_string())
+        );
+        assert_eq!(
+            estimate["cost"],
+            json!(NearToken::from_yoctonear(
+                10u128.pow(19) * u128::from(storage_after - storage_before)
+            ))
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_init_minimum_covers_one_token() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let nft_contract = common::deploy_nft_contract(&worker).await?;
+
+    let res = nft_contract
+        .call("new")
+        .args_json(json!({
+            "owner_id": nft_contract.id(),
+            "metadata": common::contract_metadata(),
+            "storage_balance_bounds": bounds(0, 30),
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(
+        format!("{err:?}").contains("Minimum storage balance must cover the storage of one token")
+    );
+
+    Ok(())
+}
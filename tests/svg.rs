@@ -0,0 +1,104 @@
+pub mod common;
+
+use near_sdk::{
+    base64::{Engine, engine::general_purpose::STANDARD},
+    serde_json::json,
+};
+use near_sdk_contract_tools::nft::Token;
+use near_workspaces::{Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::events::SvgTemplateUpdateLog;
+
+const TEMPLATE: &str = concat!(
+    r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">"#,
+    r#"<rect width="100" height="100" fill="{{color}}"/>"#,
+    r#"<text x="10" y="50">#{{token_id}} {{name}}</text>"#,
+    r#"<text x="10" y="70">{{missing}}</text></svg>"#,
+);
+
+#[tokio::test]
+async fn test_media_is_rendered_from_svg_template() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = nft_contract
+        .call("nft_mint")
+        .args_json(json!({
+            "token_id": "id-0",
+            "metadata": {"title": "Generated", "media": "ipfs://replaced"},
+            "traits": [
+                {"key": "color", "value": "red"},
+                {"key": "name", "value": "A & B"},
+            ],
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(21))
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = nft_contract
+        .call("set_svg_template")
+        .args_json(json!({"template": "<html></html>"}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("SVG template must start with an <svg> element"));
+
+    let res = nft_contract
+        .call("set_svg_template")
+        .args_json(json!({"template": TEMPLATE}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let updates: Vec<SvgTemplateUpdateLog> = common::contract_events(&res, "svg_template_update");
+    assert_eq!(updates.len(), 1);
+
+    let svg: Option<String> = nft_contract
+        .call("nft_svg")
+        .args_json(json!({"token_id": "id-0"}))
+        .view()
+        .await?
+        .json()?;
+    let expected = include_str!("fixtures/token.svg").trim_end();
+    assert_eq!(svg.as_deref(), Some(expected));
+
+    let media = format!("data:image/svg+xml;base64,{}", STANDARD.encode(expected));
+
+    let token: Token = nft_contract
+        .call("nft_token")
+        .args_json(json!({"token_id": "id-0"}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(token.extensions_metadata["metadata"]["media"], json!(media));
+
+    let tokens: Vec<Token> = nft_contract.call("nft_tokens").view().await?.json()?;
+    assert_eq!(
+        tokens[0].extensions_metadata["metadata"]["media"],
+        json!(media)
+    );
+
+    // Without a template, the stored media is back.
+    let res = nft_contract
+        .call("set_svg_template")
+        .args_json(json!({"template": null}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let token: Token = nft_contract
+        .call("nft_token")
+        .args_json(json!({"token_id": "id-0"}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(
+        token.extensions_metadata["metadata"]["media"],
+        json!("ipfs://replaced")
+    );
+
+    Ok(())
+}
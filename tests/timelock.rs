@@ -0,0 +1,334 @@
+pub mod common;
+
+use near_sdk::{
+    json_types::{Base58CryptoHash, Base64VecU8},
+    serde_json::{Value, json},
+};
+use near_workspaces::{Contract, Worker, network::Sandbox, types::NearToken};
+use sha2::{Digest, Sha256};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+
+async fn set_timelock_delay(nft_contract: &Contract, delay_seconds: u32) -> anyhow::Result<()> {
+    let res = nft_contract
+        .call("set_timelock_delay")
+        .args_json(json!({ "delay_seconds": delay_seconds }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_timelocked_actions_are_queued() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    set_timelock_delay(&nft_contract, 3600).await?;
+
+    // Direct calls are rejected once a delay is set.
+    let res = nft_contract
+        .call("set_access_mode")
+        .args_json(json!({"mode": "blocklist"}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("This action is timelocked"));
+
+    for (method, args) in [
+        (
+            "grant_role",
+            json!({"account_id": "alice.near", "role": "moderator"}),
+        ),
+        (
+            "revoke_role",
+            json!({"account_id": "alice.near", "role": "moderator"}),
+        ),
+        ("set_multisig_config", json!({"config": null})),
+        ("withdraw_sponsor_pool", json!({})),
+        ("set_history_retention", json!({"retention": 1})),
+        ("remove_redeem_recipe", json!({"recipe_id": "recipe"})),
+    ] {
+        let res = nft_contract
+            .call(method)
+            .args_json(args)
+            .max_gas()
+            .transact()
+            .await?;
+        let err = res.into_result().unwrap_err();
+        assert!(format!("{err:?}").contains("This action is timelocked"));
+    }
+
+    let res = nft_contract
+        .call("queue_admin_action")
+        .args_json(json!({"action": {"set_access_mode": "blocklist"}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let action_id = res.json::<u64>()?;
+
+    let queued: Vec<(u64, Value)> = nft_contract
+        .call("admin_actions")
+        .args_json(json!({}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].0, action_id);
+    assert_eq!(
+        queued[0].1["action"],
+        json!({"set_access_mode": "blocklist"})
+    );
+
+    let res = nft_contract
+        .call("execute_admin_action")
+        .args_json(json!({"action_id": action_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Admin action is still timelocked"));
+
+    let res = nft_contract
+        .call("cancel_admin_action")
+        .args_json(json!({"action_id": action_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let queued: Option<Value> = nft_contract
+        .call("admin_action")
+        .args_json(json!({"action_id": action_id}))
+        .view()
+        .await?
+        .json()?;
+    assert!(queued.is_none());
+
+    let mode: String = nft_contract.call("access_mode").view().await?.json()?;
+    assert_eq!(mode, "open");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_role_holders_act_without_timelock() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        "id-0".into(),
+        None,
+    )
+    .await?;
+
+    for role in ["moderator", "pauser"] {
+        let res = nft_contract
+            .call("grant_role")
+            .args_json(json!({"account_id": alice.id(), "role": role}))
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    set_timelock_delay(&nft_contract, 3600).await?;
+
+    let res = alice
+        .call(nft_contract.id(), "block_accounts")
+        .args_json(json!({"account_ids": ["bob.near"]}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(nft_contract.id(), "set_token_lockup")
+        .args_json(json!({"token_id": "id-0", "policy": null}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let status: Value = nft_contract
+        .call("account_status")
+        .args_json(json!({"account_id": "bob.near"}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(status["blocked"], json!(true));
+
+    // Handing out a role is timelocked, so the owner cannot get around the
+    // delay by granting one.
+    let res = nft_contract
+        .call("queue_admin_action")
+        .args_json(json!({"action": {"grant_role": ["bob.near", "minter"]}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let action_id = res.json::<u64>()?;
+
+    let res = nft_contract
+        .call("execute_admin_action")
+        .args_json(json!({"action_id": action_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Admin action is still timelocked"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queued_action_executes_without_delay() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = nft_contract
+        .call("queue_admin_action")
+        .args_json(json!({"action": {"set_access_mode": "blocklist"}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let action_id = res.json::<u64>()?;
+
+    let res = nft_contract
+        .call("execute_admin_action")
+        .args_json(json!({"action_id": action_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let mode: String = nft_contract.call("access_mode").view().await?.json()?;
+    assert_eq!(mode, "blocklist");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ownership_handover_waits_for_timelock() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    // Without a delay, the proposed owner can accept right away.
+    let res = nft_contract
+        .call("own_propose_owner")
+        .args_json(json!({"account_id": alice.id()}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(nft_contract.id(), "own_accept_owner")
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(nft_contract.id(), "set_timelock_delay")
+        .args_json(json!({"delay_seconds": 3600}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(nft_contract.id(), "own_propose_owner")
+        .args_json(json!({"account_id": bob.id()}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = bob
+        .call(nft_contract.id(), "own_accept_owner")
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Ownership cannot be accepted before"));
+
+    // Renouncing has to be queued, too.
+    let res = alice
+        .call(nft_contract.id(), "own_renounce_owner")
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let owner: Option<String> = nft_contract.call("own_get_owner").view().await?.json()?;
+    assert_eq!(owner, Some(alice.id().to_string()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upgrade_deploys_code_with_queued_hash() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let code = std::fs::read("tests/contracts/token-receiver/res/token_receiver.wasm")?;
+    let code_hash: [u8; 32] = Sha256::digest(&code).into();
+
+    let res = nft_contract
+        .call("queue_admin_action")
+        .args_json(json!({"action": {"upgrade": Base58CryptoHash::from(code_hash)}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let action_id = res.json::<u64>()?;
+
+    let res = nft_contract
+        .call("execute_admin_action")
+        .args_json(json!({"action_id": action_id}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Pass the code to deploy"));
+
+    let res = nft_contract
+        .call("execute_admin_action")
+        .args_json(json!({
+            "action_id": action_id,
+            "code": Base64VecU8::from(b"not the code".to_vec()),
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Code does not match the queued hash"));
+
+    let res = nft_contract
+        .call("execute_admin_action")
+        .args_json(json!({"action_id": action_id, "code": Base64VecU8::from(code)}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let account = nft_contract.view_account().await?;
+    assert_eq!(account.code_hash.0, code_hash);
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_sdk_contract_tools::nft::Token;
+use near_workspaces::{Contract, Worker, network::Sandbox, types::NearToken};
+use std::collections::BTreeMap;
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+
+async fn mint_with_traits(
+    nft_contract: &Contract,
+    token_id: &str,
+    traits: Value,
+) -> anyhow::Result<near_workspaces::result::ExecutionFinalResult> {
+    Ok(nft_contract
+        .call("nft_mint")
+        .args_json(json!({
+            "token_id": token_id,
+            "metadata": {"title": token_id},
+            "traits": traits,
+        }))
+        .max_gas()
+        .deposit(NearToken::from_millinear(21))
+        .transact()
+        .await?)
+}
+
+async fn tokens_with_trait(
+    nft_contract: &Contract,
+    key: &str,
+    value: &str,
+) -> anyhow::Result<Vec<String>> {
+    let tokens: Vec<Token> = nft_contract
+        .call("nft_tokens_with_trait")
+        .args_json(json!({"key": key, "value": value}))
+        .view()
+        .await?
+        .json()?;
+
+    Ok(tokens.into_iter().map(|token| token.token_id).collect())
+}
+
+async fn trait_counts(nft_contract: &Contract, key: &str) -> anyhow::Result<BTreeMap<String, u32>> {
+    Ok(nft_contract
+        .call("nft_trait_counts")
+        .args_json(json!({"key": key}))
+        .view()
+        .await?
+        .json()?)
+}
+
+#[tokio::test]
+async fn test_traits_are_stored_and_indexed() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    for (token_id, color) in [("id-0", "red"), ("id-1", "blue"), ("id-2", "red")] {
+        let res = mint_with_traits(
+            &nft_contract,
+            token_id,
+            json!([
+                {"key": "color", "value": color},
+                {"key": "size", "value": "large"},
+            ]),
+        )
+        .await?;
+        assert!(res.is_success());
+    }
+
+    let token: Token = nft_contract
+        .call("nft_token")
+        .args_json(json!({"token_id": "id-1"}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(
+        token.extensions_metadata["traits"],
+        json!([
+            {"key": "color", "value": "blue"},
+            {"key": "size", "value": "large"},
+        ])
+    );
+
+    assert_eq!(
+        tokens_with_trait(&nft_contract, "color", "red").await?,
+        vec!["id-0", "id-2"]
+    );
+    assert!(
+        tokens_with_trait(&nft_contract, "color", "green")
+            .await?
+            .is_empty()
+    );
+    assert_eq!(
+        trait_counts(&nft_contract, "color").await?,
+        BTreeMap::from([("blue".to_string(), 1), ("red".to_string(), 2)])
+    );
+
+    // Values are paged in the order they were first used.
+    let page: BTreeMap<String, u32> = nft_contract
+        .call("nft_trait_counts")
+        .args_json(json!({"key": "color", "from_index": "1", "limit": 1}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(page, BTreeMap::from([("blue".to_string(), 1)]));
+
+    let count: u32 = nft_contract
+        .call("nft_trait_count")
+        .args_json(json!({"key": "color", "value": "red"}))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(count, 2);
+
+    // Burned tokens leave the index.
+    let res = nft_contract
+        .call("nft_burn")
+        .args_json(json!({"token_id": "id-0"}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    assert_eq!(
+        tokens_with_trait(&nft_contract, "color", "red").await?,
+        vec!["id-2"]
+    );
+    assert_eq!(
+        trait_counts(&nft_contract, "size").await?,
+        BTreeMap::from([("large".to_string(), 2)])
+    );
+
+    // Burning the last token with a value removes the value.
+    let res = nft_contract
+        .call("nft_burn")
+        .args_json(json!({"token_id": "id-1"}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    assert_eq!(
+        trait_counts(&nft_contract, "color").await?,
+        BTreeMap::from([("red".to_string(), 1)])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_traits_are_rejected() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    let res = mint_with_traits(
+        &nft_contract,
+        "id-0",
+        json!([
+            {"key": "color", "value": "red"},
+            {"key": "color", "value": "blue"},
+        ]),
+    )
+    .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Trait `color` is set more than once"));
+
+    let res = mint_with_traits(&nft_contract, "id-0", json!([{"key": "", "value": "red"}])).await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Trait keys cannot be empty"));
+
+    Ok(())
+}
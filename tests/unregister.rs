@@ -0,0 +1,238 @@
+pub mod common;
+
+use near_sdk::serde_json::{Value, json};
+use near_sdk_contract_tools::nft::Token;
+use near_workspaces::{Contract, Worker, network::Sandbox, types::NearToken};
+use nft_contract_tools::events::UnregisterPolicyUpdateLog;
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const TOKEN_ID: &str = "0";
+
+async fn token(nft_contract: &Contract) -> anyhow::Result<Option<Token>> {
+    Ok(nft_contract
+        .call("nft_token")
+        .args_json(json!({"token_id": TOKEN_ID}))
+        .view()
+        .await?
+        .json()?)
+}
+
+#[tokio::test]
+async fn test_unregister_refused_while_owning_tokens() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+
+    let res = alice
+        .call(nft_contract.id(), "storage_unregister")
+        .args_json(json!({}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Cannot unregister while owning tokens"));
+
+    assert!(token(&nft_contract).await?.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_force_unregister_burns_tokens() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+
+    let balance_before = alice.view_account().await?.balance;
+
+    let res = alice
+        .call(nft_contract.id(), "storage_unregister")
+        .args_json(json!({"force": true}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(res.json::<bool>()?);
+
+    assert!(token(&nft_contract).await?.is_none());
+
+    let storage: Option<Value> = nft_contract
+        .call("storage_balance_of")
+        .args_json((alice.id(),))
+        .view()
+        .await?
+        .json()?;
+    assert!(storage.is_none());
+
+    // The whole 0.021 NEAR deposit comes back, less gas.
+    let balance_after = alice.view_account().await?.balance;
+    assert!(balance_after > balance_before.saturating_add(NearToken::from_millinear(15)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_force_unregister_transfers_tokens_to_sink() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let sink = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    // The sink has to be registered first.
+    let res = nft_contract
+        .call("set_unregister_policy")
+        .args_json(json!({"policy": {"transfer_to": sink.id()}}))
+        .max_gas()
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("Sink account must be registered for storage"));
+
+    common::register_user(&nft_contract, sink.id()).await?;
+
+    let res = nft_contract
+        .call("set_unregister_policy")
+        .args_json(json!({"policy": {"transfer_to": sink.id()}}))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let updates: Vec<UnregisterPolicyUpdateLog> =
+        common::contract_events(&res, "unregister_policy_update");
+    assert_eq!(updates.len(), 1);
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+
+    let res = alice
+        .call(nft_contract.id(), "storage_unregister")
+        .args_json(json!({"force": true}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let token = token(&nft_contract)
+        .await?
+        .expect("token should still exist");
+    assert_eq!(token.owner_id.as_str(), sink.id().as_str());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_force_unregister_hands_locked_token_to_sink() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let sink = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    common::register_user(&nft_contract, sink.id()).await?;
+
+    for (method, args) in [
+        (
+            "set_unregister_policy",
+            json!({"policy": {"transfer_to": sink.id()}}),
+        ),
+        (
+            "set_collection_lockup",
+            json!({"policy": {"cooldown_seconds": 3600}}),
+        ),
+    ] {
+        let res = nft_contract
+            .call(method)
+            .args_json(args)
+            .max_gas()
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    common::mint_nft(
+        nft_contract.as_account(),
+        nft_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+
+    let res = alice
+        .call(nft_contract.id(), "storage_unregister")
+        .args_json(json!({"force": true}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let token = token(&nft_contract)
+        .await?
+        .expect("token should still exist");
+    assert_eq!(token.owner_id.as_str(), sink.id().as_str());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_force_unregister_rejects_nested_tokens() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+
+    for token_id in [TOKEN_ID, "1"] {
+        common::mint_nft(
+            nft_contract.as_account(),
+            nft_contract.id(),
+            token_id.into(),
+            Some(alice.id()),
+        )
+        .await?;
+    }
+
+    let res = alice
+        .call(nft_contract.id(), "nft_nest")
+        .args_json(json!({"token_id": "1", "parent_token_id": TOKEN_ID}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(nft_contract.id(), "storage_unregister")
+        .args_json(json!({"force": true}))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    let err = res.into_result().unwrap_err();
+    assert!(format!("{err:?}").contains("has nested tokens; unnest them before unregistering"));
+
+    assert!(token(&nft_contract).await?.is_some());
+
+    Ok(())
+}
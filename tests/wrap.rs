@@ -0,0 +1,310 @@
+pub mod common;
+
+use near_sdk::serde_json::json;
+use near_sdk_contract_tools::nft::Token;
+use near_workspaces::{Account, Contract, Worker, network::Sandbox, types::NearToken};
+
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+const TOKEN_ID: &str = "external-0";
+
+async fn accept_wraps_from(
+    nft_contract: &Contract,
+    external_contract: &Contract,
+) -> anyhow::Result<()> {
+    let res = nft_contract
+        .call("set_receiver_policy")
+        .args_json(json!({
+            "policy": {
+                "accept_any_contract": false,
+                "accepted_contracts": [external_contract.id()],
+                "allowed_actions": ["wrap"],
+            }
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+async fn send_to_wrap(
+    owner: &Account,
+    nft_contract: &Contract,
+    external_contract: &Contract,
+) -> anyhow::Result<()> {
+    let res = owner
+        .call(external_contract.id(), "nft_transfer_call")
+        .args_json((
+            nft_contract.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+            "wrap",
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    Ok(())
+}
+
+async fn nft_token(contract: &Contract, token_id: &str) -> anyhow::Result<Option<Token>> {
+    Ok(contract
+        .call("nft_token")
+        .args_json((token_id,))
+        .view()
+        .await?
+        .json()?)
+}
+
+#[tokio::test]
+async fn test_wrap_and_release_external_token() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+    let external_contract = common::init_nft_contract(&worker).await?;
+
+    common::mint_nft(
+        external_contract.as_account(),
+        external_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&external_contract, nft_contract.id()).await?;
+    common::register_user(&nft_contract, alice.id()).await?;
+    accept_wraps_from(&nft_contract, &external_contract).await?;
+
+    send_to_wrap(&alice, &nft_contract, &external_contract).await?;
+
+    let original = nft_token(&external_contract, TOKEN_ID).await?.unwrap();
+    assert_eq!(original.owner_id.to_string(), nft_contract.id().to_string());
+
+    let wrapped_token_id = format!("{}:{}", external_contract.id(), TOKEN_ID);
+    let wrapped = nft_token(&nft_contract, &wrapped_token_id).await?.unwrap();
+    assert_eq!(wrapped.owner_id.to_string(), alice.id().to_string());
+    assert_eq!(
+        wrapped.extensions_metadata["metadata"],
+        original.extensions_metadata["metadata"]
+    );
+
+    // Burning the wrapped token sends the original back.
+    let res = alice
+        .call(nft_contract.id(), "nft_burn")
+        .args_json((&wrapped_token_id,))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    assert!(nft_token(&nft_contract, &wrapped_token_id).await?.is_none());
+    let original = nft_token(&external_contract, TOKEN_ID).await?.unwrap();
+    assert_eq!(original.owner_id.to_string(), alice.id().to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrapped_tokens_cannot_be_redeemed() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+    let external_contract = common::init_nft_contract(&worker).await?;
+
+    common::mint_nft(
+        external_contract.as_account(),
+        external_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&external_contract, nft_contract.id()).await?;
+    common::register_user(&nft_contract, alice.id()).await?;
+    accept_wraps_from(&nft_contract, &external_contract).await?;
+
+    let res = nft_contract
+        .call("add_redeem_recipe")
+        .args_json(json!({
+            "recipe_id": "wrapped",
+            "recipe": {
+                "inputs": [{"series": external_contract.id()}],
+                "output_metadata": {"title": "Redeemed"},
+                "output_token_id_prefix": "redeemed-",
+                "max_redemptions": null,
+                "starts_at": null,
+                "ends_at": null,
+            },
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let wrapped_token_id = format!("{}:{}", external_contract.id(), TOKEN_ID);
+
+    // Wrap, redeem, unwrap and wrap again: neither redemption goes through,
+    // so one original can never be turned into several outputs.
+    for _ in 0..2 {
+        send_to_wrap(&alice, &nft_contract, &external_contract).await?;
+
+        let res = alice
+            .call(nft_contract.id(), "nft_redeem")
+            .args_json(json!({"recipe_id": "wrapped", "token_ids": [&wrapped_token_id]}))
+            .max_gas()
+            .transact()
+            .await?;
+        let err = res.into_result().unwrap_err();
+        assert!(format!("{err:?}").contains("cannot be redeemed"));
+
+        let wrapped = nft_token(&nft_contract, &wrapped_token_id).await?.unwrap();
+        assert_eq!(wrapped.owner_id.to_string(), alice.id().to_string());
+        let original = nft_token(&external_contract, TOKEN_ID).await?.unwrap();
+        assert_eq!(original.owner_id.to_string(), nft_contract.id().to_string());
+
+        let res = alice
+            .call(nft_contract.id(), "nft_burn")
+            .args_json((&wrapped_token_id,))
+            .max_gas()
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    assert!(nft_token(&nft_contract, "redeemed-1").await?.is_none());
+    let original = nft_token(&external_contract, TOKEN_ID).await?.unwrap();
+    assert_eq!(original.owner_id.to_string(), alice.id().to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrap_requires_storage_registration() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+    let external_contract = common::init_nft_contract(&worker).await?;
+
+    common::mint_nft(
+        external_contract.as_account(),
+        external_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&external_contract, nft_contract.id()).await?;
+    accept_wraps_from(&nft_contract, &external_contract).await?;
+
+    send_to_wrap(&alice, &nft_contract, &external_contract).await?;
+
+    let original = nft_token(&external_contract, TOKEN_ID).await?.unwrap();
+    assert_eq!(original.owner_id.to_string(), alice.id().to_string());
+
+    let wrapped_token_id = format!("{}:{}", external_contract.id(), TOKEN_ID);
+    assert!(nft_token(&nft_contract, &wrapped_token_id).await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_burn_to_mint_external_token() -> anyhow::Result<()> {
+    let worker: Worker<Sandbox> = near_workspaces::sandbox().await?;
+    let alice = worker.dev_create_account().await?;
+    let (nft_contract, _, _) = common::init_contracts(&worker).await?;
+    let external_contract = common::init_nft_contract(&worker).await?;
+
+    common::mint_nft(
+        external_contract.as_account(),
+        external_contract.id(),
+        TOKEN_ID.into(),
+        Some(alice.id()),
+    )
+    .await?;
+    common::register_user(&external_contract, nft_contract.id()).await?;
+    common::register_user(&nft_contract, alice.id()).await?;
+
+    let res = nft_contract
+        .call("set_receiver_policy")
+        .args_json(json!({
+            "policy": {
+                "accept_any_contract": false,
+                "accepted_contracts": [external_contract.id()],
+                "allowed_actions": ["burn_to_mint"],
+            }
+        }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = alice
+        .call(external_contract.id(), "nft_transfer_call")
+        .args_json((
+            nft_contract.id(),
+            TOKEN_ID,
+            Option::<u64>::None,
+            Option::<String>::None,
+            "burn_to_mint",
+        ))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // The original stays with this contract for good.
+    let original = nft_token(&external_contract, TOKEN_ID).await?.unwrap();
+    assert_eq!(original.owner_id.to_string(), nft_contract.id().to_string());
+
+    let replacement_id = format!("{}:{}", external_contract.id(), TOKEN_ID);
+    let replacement = nft_token(&nft_contract, &replacement_id).await?.unwrap();
+    assert_eq!(replacement.owner_id.to_string(), alice.id().to_string());
+
+    let source: Option<near_sdk::serde_json::Value> = nft_contract
+        .call("burned_token_source")
+        .args_json((&replacement_id,))
+        .view()
+        .await?
+        .json()?;
+    assert_eq!(
+        source,
+        Some(json!({"contract_id": external_contract.id(), "token_id": TOKEN_ID}))
+    );
+
+    // It is neither a withdrawable deposit nor released by burning the
+    // replacement.
+    let res = alice
+        .call(nft_contract.id(), "nft_withdraw_external")
+        .args_json((external_contract.id(), TOKEN_ID))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+
+    let res = alice
+        .call(nft_contract.id(), "nft_burn")
+        .args_json((&replacement_id,))
+        .max_gas()
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let original = nft_token(&external_contract, TOKEN_ID).await?.unwrap();
+    assert_eq!(original.owner_id.to_string(), nft_contract.id().to_string());
+
+    let source: Option<near_sdk::serde_json::Value> = nft_contract
+        .call("burned_token_source")
+        .args_json((&replacement_id,))
+        .view()
+        .await?
+        .json()?;
+    assert!(source.is_none());
+
+    Ok(())
+}